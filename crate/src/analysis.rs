@@ -0,0 +1,254 @@
+//! Board fairness analysis. Random boards can hand one player a big head start so we
+//! measure each player's holdings and, if asked, keep rolling boards until they're even.
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::fmt;
+
+use dicey_dice::game::{self, Board};
+
+use crate::grid::{self, Template};
+use crate::map::NEUTRAL;
+
+/// How many boards will be rolled looking for a balanced one before settling for the
+/// most balanced board seen so far.
+pub const BALANCE_ATTEMPTS: usize = 200;
+
+/// What one player starts a board with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PlayerStats {
+    pub player: usize,
+
+    /// Number of hexes owned.
+    pub hexes: u32,
+
+    /// Total dice across all owned hexes.
+    pub dice: u32,
+
+    /// Hex count of the biggest group of owned hexes that touch each other.
+    pub largest_region: u32,
+
+    /// Number of owned hexes that border an enemy hex.
+    pub frontier: u32,
+}
+
+impl PlayerStats {
+    fn new(player: usize) -> Self {
+        PlayerStats {
+            player,
+            hexes: 0,
+            dice: 0,
+            largest_region: 0,
+            frontier: 0,
+        }
+    }
+}
+
+impl fmt::Display for PlayerStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Player{}: {} hexes, {} dice, largest region {}, frontier {}",
+            self.player, self.hexes, self.dice, self.largest_region, self.frontier,
+        )
+    }
+}
+
+/// Biggest allowed difference between the best and worst off players for each measure.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Tolerance {
+    pub hexes: u32,
+    pub dice: u32,
+    pub largest_region: u32,
+    pub frontier: u32,
+}
+
+impl Tolerance {
+    /// Same allowed difference for every measure.
+    pub fn uniform(spread: u32) -> Self {
+        Tolerance {
+            hexes: spread,
+            dice: spread,
+            largest_region: spread,
+            frontier: spread,
+        }
+    }
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            hexes: 1,
+            dice: 3,
+            largest_region: 2,
+            frontier: 2,
+        }
+    }
+}
+
+/// Produce the stats for every player holding at least one hex on the `board`. Hexes are
+/// matched to the `template` by coordinate and only touch when the `template` says they
+/// do. Stats are ordered by player number. Blocked hexes belong to no one and are left
+/// out.
+pub fn analyse(board: &Board, template: &Template) -> Vec<PlayerStats> {
+    let (owners, dice): (Vec<usize>, Vec<u8>) = grid::holdings(template, board)
+        .into_iter()
+        .unzip();
+
+    let mut stats: BTreeMap<usize, PlayerStats> = BTreeMap::new();
+    let mut visited: HashSet<usize> = HashSet::new();
+
+    for (index, owner) in owners.iter().enumerate() {
+        if *owner == NEUTRAL {
+            continue;
+        }
+
+        let entry = stats
+            .entry(*owner)
+            .or_insert_with(|| PlayerStats::new(*owner));
+
+        entry.hexes += 1;
+        entry.dice += dice[index] as u32;
+
+        if template
+            .neighbours(index)
+            .any(|n| owners[n] != *owner && owners[n] != NEUTRAL) {
+            entry.frontier += 1;
+        }
+
+        if visited.insert(index) {
+            let region = flood_region(&owners, template, index, &mut visited);
+            entry.largest_region = entry.largest_region.max(region);
+        }
+    }
+
+    stats.into_iter().map(|(_, s)| s).collect()
+}
+
+/// Count the hexes connected to `start` that share its owner, marking them as `visited`.
+fn flood_region(
+    owners: &[usize], template: &Template, start: usize, visited: &mut HashSet<usize>,
+) -> u32 {
+    let owner = owners[start];
+    let mut queue = VecDeque::new();
+    let mut count = 0;
+    queue.push_back(start);
+
+    while let Some(index) = queue.pop_front() {
+        count += 1;
+        template
+            .neighbours(index)
+            .filter(|n| owners[*n] == owner)
+            .for_each(|n| {
+                if visited.insert(n) {
+                    queue.push_back(n);
+                }
+            });
+    }
+
+    count
+}
+
+fn spread<F>(stats: &[PlayerStats], measure: F) -> u32
+where F: Fn(&PlayerStats) -> u32 {
+    let max = stats.iter().map(&measure).max().unwrap_or(0);
+    let min = stats.iter().map(&measure).min().unwrap_or(0);
+    max - min
+}
+
+/// Sum of all the spreads. Lower is fairer.
+pub fn imbalance(stats: &[PlayerStats]) -> u32 {
+    spread(stats, |s| s.hexes)
+        + spread(stats, |s| s.dice)
+        + spread(stats, |s| s.largest_region)
+        + spread(stats, |s| s.frontier)
+}
+
+/// Check if all players are within the `tolerance` of each other.
+pub fn within(stats: &[PlayerStats], tolerance: &Tolerance) -> bool {
+    spread(stats, |s| s.hexes) <= tolerance.hexes
+        && spread(stats, |s| s.dice) <= tolerance.dice
+        && spread(stats, |s| s.largest_region) <= tolerance.largest_region
+        && spread(stats, |s| s.frontier) <= tolerance.frontier
+}
+
+/// Keep rolling random boards until one is found where the `players` are within the
+/// `tolerance`. Gives up after `attempts` and returns the most balanced board rolled.
+/// The boards are the size of the `template` and `terrain` is laid over every roll before
+/// it is judged so that hexes off the board or under water don't count towards anyone.
+pub fn generate_balanced_board<F>(
+    template: &Template,
+    players: game::Players,
+    tolerance: &Tolerance,
+    attempts: usize,
+    terrain: F,
+) -> Board
+where F: Fn(Board) -> Board {
+    let mut best: Option<(u32, Board)> = None;
+
+    for _ in 0..attempts.max(1) {
+        let board = terrain(game::generate_random_board(
+            template.columns(), template.rows(), players.clone(),
+        ));
+        let stats = analyse(&board, template);
+
+        if within(&stats, tolerance) {
+            return board;
+        }
+
+        let score = imbalance(&stats);
+        let better = best
+            .as_ref()
+            .map(|(best_score, _)| score < *best_score)
+            .unwrap_or(true);
+        if better {
+            best = Some((score, board));
+        }
+    }
+
+    best.map(|(_, board)| board).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU8;
+
+    use super::*;
+    use crate::grid::Shape;
+    use crate::map::Map;
+    use crate::notation::Snapshot;
+    use crate::primitive::{Orientation, Point};
+
+    #[test]
+    fn hexes_off_the_board_touch_nothing() {
+        let map = Map::parse("size: 3x2\nA2 .  B1\nA1 A1 B3\n").unwrap();
+        let template = map.template(Point::default(), 10, Orientation::Pointy);
+        let stats = analyse(&map.board(), &template);
+        let a = PlayerStats {
+            player: 1, hexes: 3, dice: 4, largest_region: 3, frontier: 1,
+        };
+        let b = PlayerStats {
+            player: 2, hexes: 2, dice: 4, largest_region: 2, frontier: 2,
+        };
+        assert_eq!(stats, vec![a, b]);
+        assert_eq!(template.neighbours(0).collect::<Vec<usize>>(), vec![3]);
+    }
+
+    #[test]
+    fn judges_boards_with_their_terrain() {
+        let mut template = grid::generate_template(
+            6, 6, Point::default(), 10, Orientation::Pointy,
+        );
+        let land = grid::shape_mask(Shape::Hexagon, 6, 6);
+        template.mask(&land);
+        let move_limit = NonZeroU8::new(3).unwrap();
+        let tolerance = Tolerance::uniform(6);
+
+        let board = generate_balanced_board(
+            &template, game::Players::new(2), &tolerance, BALANCE_ATTEMPTS,
+            |board| Snapshot::from_board(&board, &template, move_limit).board(),
+        );
+        let stats = analyse(&board, &template);
+        let held: u32 = stats.iter().map(|player| player.hexes).sum();
+        assert_eq!(held as usize, land.iter().filter(|on_land| **on_land).count());
+        assert!(within(&stats, &tolerance), "{:?}", stats);
+    }
+}
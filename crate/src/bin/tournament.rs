@@ -0,0 +1,107 @@
+//! Play AI versus AI games without a browser and report the win rates.
+//!
+//! ```console
+//! cargo run --features tournament --bin tournament -- \
+//!     --size 4x4 --seats 2 --horizons 2,3 --games 100 --seed 7 --format csv
+//! ```
+use std::env;
+use std::num::NonZeroU8;
+use std::process;
+
+use wasm_dicey::tournament::{self, Settings};
+
+const USAGE: &str = "\
+Usage: tournament [options]
+    --size <columns>x<rows>   Board size. Default 4x4.
+    --seats <n>               Players per game from 2 to 4. Default 2.
+    --horizons <h1,h2,..>     AI compute horizon of each profile. Default 2.
+    --games <n>               Number of games. Default 10.
+    --seed <n>                Deal the boards from a seed so the games can be played
+                              again. Default boards are from the game's generator.
+    --move-limit <n>          Moves per turn. Default 3.
+    --max-moves <n>           Moves before a game is called a stalemate. Default 500.
+    --format <csv|json>       Report format. Default csv.";
+
+enum Format {
+    Csv,
+    Json,
+}
+
+fn parse_args(args: &[String]) -> Result<(Settings, Format), String> {
+    let mut settings = Settings::default();
+    let mut format = Format::Csv;
+    let mut args = args.iter();
+
+    while let Some(flag) = args.next() {
+        if flag == "--help" || flag == "-h" {
+            return Err(String::new());
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        let bad = || format!("Bad value '{}' for {}", value, flag);
+
+        match flag.as_str() {
+            "--size" => {
+                let mut parts = value.splitn(2, 'x');
+                let mut dimension = || parts.next().and_then(|d| d.parse().ok());
+                settings.columns = dimension().ok_or_else(bad)?;
+                settings.rows = dimension().ok_or_else(bad)?;
+            },
+            "--seats" => settings.seats = value.parse().map_err(|_| bad())?,
+            "--horizons" => {
+                settings.profiles = value
+                    .split(',')
+                    .map(|h| h.trim().parse())
+                    .collect::<Result<Vec<usize>, _>>()
+                    .map_err(|_| bad())?;
+            },
+            "--games" => settings.games = value.parse().map_err(|_| bad())?,
+            "--seed" => settings.seed = Some(value.parse().map_err(|_| bad())?),
+            "--move-limit" => {
+                settings.move_limit = value
+                    .parse()
+                    .ok()
+                    .and_then(NonZeroU8::new)
+                    .ok_or_else(bad)?;
+            },
+            "--max-moves" => settings.max_moves = value.parse().map_err(|_| bad())?,
+            "--format" => {
+                format = match value.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => return Err(bad()),
+                };
+            },
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    Ok((settings, format))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (settings, format) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{}", e);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        },
+    };
+
+    match tournament::run(&settings) {
+        Ok(report) => match format {
+            Format::Csv => print!("{}", report.to_csv()),
+            Format::Json => print!("{}", report.to_json()),
+        },
+        Err(e) => {
+            eprintln!("Tournament failed: {}", e);
+            process::exit(1);
+        },
+    }
+}
@@ -0,0 +1,277 @@
+//! Play in a terminal. Handy for trying out rule and AI changes over SSH.
+//!
+//! ```console
+//! cargo run --no-default-features --features tui --bin tui -- --size 5 --players human,ai
+//! ```
+//!
+//! Hexes are picked by typing their column and row. Picking one of your hexes shows what
+//! it can attack and picking a target asks to confirm the attack.
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use wasm_dicey::{map, play::Game, GameSettings, Orientation, Point, Shape, Tessellation};
+
+const USAGE: &str = "\
+Usage: tui [options]
+    --size <n>                  Board is n by n hexes. Default 5.
+    --shape <shape>             One of rectangle, hexagon or triangle. Default rectangle.
+    --lakes <n>                 Lakes of water to scatter on the board. Default 0.
+    --seed <n>                  Seed for the lakes. Default from the clock.
+    --players <p1,p2,..>        Up to four of human, ai or none. Default human,ai.
+    --move-limit <n>            Moves per turn. Default 3.
+    --horizon <n>               AI compute horizon. Default 2.
+    --position <notation>       Start from a position instead of a random board.
+    --plain                     No colours.";
+
+const HELP: &str = "\
+    <column> <row>   Pick a hex. Pick your own hex first and then one marked with !.
+    q                Quit.";
+
+/// Terminal colour of each player. The neutral player is first.
+const COLOURS: [&str; 5] = ["\x1b[90m", "\x1b[31m", "\x1b[34m", "\x1b[32m", "\x1b[33m"];
+const RESET: &str = "\x1b[0m";
+
+struct Options {
+    size: u32,
+    shape: Shape,
+    lakes: u32,
+    seed: Option<u32>,
+    player_codes: [u8; 4],
+    move_limit: u8,
+    horizon: usize,
+    position: Option<String>,
+    colour: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            size: 5,
+            shape: Shape::Rectangle,
+            lakes: 0,
+            seed: None,
+            player_codes: [1, 2, 0, 0],
+            move_limit: 3,
+            horizon: 2,
+            position: None,
+            colour: true,
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--help" | "-h" => return Err(String::new()),
+            "--plain" => {
+                options.colour = false;
+                continue;
+            },
+            _ => (),
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        let bad = || format!("Bad value '{}' for {}", value, flag);
+
+        match flag.as_str() {
+            "--size" => {
+                options.size = value.parse().ok().filter(|s| *s > 0).ok_or_else(bad)?;
+            },
+            "--shape" => {
+                options.shape = match value.as_str() {
+                    "rectangle" => Shape::Rectangle,
+                    "hexagon" => Shape::Hexagon,
+                    "triangle" => Shape::Triangle,
+                    _ => return Err(bad()),
+                };
+            },
+            "--lakes" => options.lakes = value.parse().map_err(|_| bad())?,
+            "--seed" => options.seed = Some(value.parse().map_err(|_| bad())?),
+            "--players" => {
+                let codes = value
+                    .split(',')
+                    .map(|player| match player.trim() {
+                        "human" => Ok(1),
+                        "ai" => Ok(2),
+                        "none" => Ok(0),
+                        _ => Err(bad()),
+                    })
+                    .collect::<Result<Vec<u8>, String>>()?;
+                if codes.len() > 4 || codes.iter().filter(|code| **code != 0).count() < 2 {
+                    return Err(bad());
+                }
+                options.player_codes = [0; 4];
+                options.player_codes[..codes.len()].copy_from_slice(&codes);
+            },
+            "--move-limit" => {
+                options.move_limit = value.parse().ok().filter(|m| *m > 0).ok_or_else(bad)?;
+            },
+            "--horizon" => options.horizon = value.parse().map_err(|_| bad())?,
+            "--position" => options.position = Some(value.to_owned()),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn start_game(options: &Options) -> Result<Game, String> {
+    let [p1, p2, p3, p4] = options.player_codes;
+    let seed = options.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.subsec_nanos())
+            .unwrap_or(1)
+    });
+    match options.position.as_ref() {
+        Some(position) => wasm_dicey::start_game_from_position(
+            position, Point::default(), 1, Orientation::Pointy, p1, p2, p3, p4,
+            options.horizon,
+        ).map_err(|e| format!("{:?}", e)),
+        None => Ok(wasm_dicey::start_new_game(
+            &GameSettings::new(options.size, Point::default(), 1)
+                .with_move_limit(options.move_limit)
+                .with_players(p1, p2, p3, p4)
+                .with_ai_compute_horizon(options.horizon)
+                .with_shape(options.shape)
+                .with_lakes(options.lakes)
+                .with_seed(seed),
+        )),
+    }
+}
+
+/// The board as `Tessellation::to_text` draws it, with each player's hexes painted in
+/// their colour if `colour` is wanted.
+fn render(tessellation: &Tessellation, colour: bool) -> String {
+    let text = tessellation.to_text();
+    if !colour {
+        return text;
+    }
+
+    let mut painted = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let owner = map::player_number(c).filter(|owner| *owner < COLOURS.len());
+        match (owner, chars.peek()) {
+            (Some(owner), Some(dice)) if dice.is_ascii_digit() => {
+                painted.push_str(&format!("{}{}{}{}", COLOURS[owner], c, dice, RESET));
+                chars.next();
+            },
+            _ => painted.push(c),
+        }
+    }
+    painted
+}
+
+fn status(game: &Game) -> String {
+    format!(
+        "Player {} to move. {} moves left, {} dice captured.",
+        map::player_letter(game.current_player_id() as usize),
+        game.current_player_moves_left(),
+        game.current_player_dice_captured(),
+    )
+}
+
+/// Column and row typed as two numbers.
+fn parse_hex(line: &str, tessellation: &Tessellation) -> Option<usize> {
+    let mut parts = line
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty());
+    let column: u32 = parts.next()?.parse().ok()?;
+    let row: u32 = parts.next()?.parse().ok()?;
+    let outside = column >= tessellation.columns() || row >= tessellation.rows();
+    if parts.next().is_some() || outside {
+        return None;
+    }
+    Some((row * tessellation.columns() + column) as usize)
+}
+
+fn prompt(input: &mut impl BufRead, message: &str) -> Option<String> {
+    print!("{}", message);
+    io::stdout().flush().ok()?;
+    let mut line = String::new();
+    match input.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_owned()),
+    }
+}
+
+fn play(mut game: Game, colour: bool) {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    println!("{}", HELP);
+
+    loop {
+        while game.current_player_ai() && !game.game_over() {
+            let player = map::player_letter(game.current_player_id() as usize);
+            if !game.advance() {
+                break;
+            }
+            println!("{}", render(&game.tessellation(), colour));
+            println!("Player {} moved.", player);
+        }
+
+        let tessellation = game.tessellation();
+        println!("{}", render(&tessellation, colour));
+        if game.game_over() {
+            println!("{}", game.state_log());
+            return;
+        }
+        println!("{}", status(&game));
+
+        let line = match prompt(&mut input, "> ") {
+            Some(line) => line,
+            None => return,
+        };
+        if line == "q" {
+            return;
+        }
+
+        let index = match parse_hex(&line, &tessellation) {
+            Some(index) => index,
+            None => {
+                println!("{}", HELP);
+                continue;
+            },
+        };
+
+        // Only attacks need confirming. Anything else just changes the selection.
+        if game.selected_index().is_some() && tessellation.hex(index).threatened() {
+            match prompt(&mut input, "Attack? [y/N] ") {
+                Some(ref answer) if answer == "y" => (),
+                Some(_) => continue,
+                None => return,
+            }
+        }
+        game.select_hex_with_index(index);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{}", e);
+            }
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        },
+    };
+
+    match start_game(&options) {
+        Ok(game) => play(game, options.colour),
+        Err(e) => {
+            eprintln!("Failed to start the game: {}", e);
+            process::exit(1);
+        },
+    }
+}
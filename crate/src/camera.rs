@@ -0,0 +1,138 @@
+//! Panning and zooming around boards too big for the canvas. A `Camera` maps board pixels,
+//! where a `Tessellation` is laid out, onto screen pixels. The renderers draw through it
+//! and clicks are taken back through it before hit testing so the two always agree.
+//!
+//! On high DPI screens the board is laid out at the canvas backing store resolution, which
+//! is `pixel_ratio` times the CSS pixels the page works in. The camera itself works in CSS
+//! pixels and only the renderers scale up to the backing store.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::grid::Tessellation;
+use crate::primitive::{Point, PrecisePoint};
+
+/// Furthest the camera will zoom out.
+const MIN_SCALE: f64 = 0.05;
+
+/// Furthest the camera will zoom in.
+const MAX_SCALE: f64 = 8.0;
+
+/// Board points are scaled by `scale` then moved by `(x, y)` to land on the screen.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera {
+    x: f64,
+    y: f64,
+    scale: f64,
+
+    /// Backing store pixels per CSS pixel, `window.devicePixelRatio` in the browser.
+    pixel_ratio: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera::new()
+    }
+}
+
+impl Camera {
+    pub fn screen_from_board(&self, board: PrecisePoint) -> PrecisePoint {
+        board * self.scale + PrecisePoint::new(self.x, self.y)
+    }
+
+    pub fn board_from_screen(&self, screen: PrecisePoint) -> PrecisePoint {
+        (screen - PrecisePoint::new(self.x, self.y)) * (1_f64 / self.scale)
+    }
+
+    /// The `(x, y, scale)` that take tessellation pixels straight to backing store pixels
+    /// for the renderers.
+    pub fn backing_transform(&self) -> (f64, f64, f64) {
+        (self.x * self.pixel_ratio, self.y * self.pixel_ratio, self.scale)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Camera {
+    /// Board pixels are screen pixels.
+    pub fn new() -> Self {
+        Camera { x: 0_f64, y: 0_f64, scale: 1_f64, pixel_ratio: 1_f64 }
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn pixel_ratio(&self) -> f64 {
+        self.pixel_ratio
+    }
+
+    /// Boards are laid out `pixel_ratio` times larger than they appear in CSS pixels.
+    /// Ratios that aren't positive are taken as one.
+    pub fn set_pixel_ratio(&mut self, pixel_ratio: f64) {
+        self.pixel_ratio = if pixel_ratio > 0_f64 { pixel_ratio } else { 1_f64 };
+    }
+
+    /// Move the view by a number of screen pixels, such as how far the mouse was dragged.
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+    }
+
+    /// Zoom in by `factor`, or out when it's below one, keeping whatever is under the
+    /// `screen` point where it is. Handy for zooming around the mouse.
+    pub fn zoom_at(&mut self, factor: f64, screen: Point) {
+        let screen = PrecisePoint::from(screen);
+        let anchor = self.board_from_screen(screen);
+        self.scale = (self.scale * factor).max(MIN_SCALE).min(MAX_SCALE);
+        let moved = self.screen_from_board(anchor);
+        self.pan(screen.x() - moved.x(), screen.y() - moved.y());
+    }
+
+    /// Zoom and pan so the whole `tessellation` fits a `width` by `height` screen with
+    /// `margin` pixels to spare on every side, centered. All in CSS pixels.
+    pub fn fit(
+        &mut self, tessellation: &Tessellation, width: u32, height: u32, margin: u32,
+    ) {
+        let (top_left, bottom_right) = tessellation.bounds();
+        let to_css = 1_f64 / self.pixel_ratio;
+        let top_left = PrecisePoint::from(top_left) * to_css;
+        let bottom_right = PrecisePoint::from(bottom_right) * to_css;
+        let board_width = (bottom_right.x() - top_left.x()).max(1_f64);
+        let board_height = (bottom_right.y() - top_left.y()).max(1_f64);
+        let room_width = (width as f64 - 2_f64 * margin as f64).max(1_f64);
+        let room_height = (height as f64 - 2_f64 * margin as f64).max(1_f64);
+
+        self.scale = (room_width / board_width)
+            .min(room_height / board_height)
+            .max(MIN_SCALE)
+            .min(MAX_SCALE);
+        let middle = (top_left + bottom_right) * 0.5;
+        self.x = width as f64 / 2_f64 - middle.x() * self.scale;
+        self.y = height as f64 / 2_f64 - middle.y() * self.scale;
+    }
+
+    /// Back to board pixels being screen pixels. The pixel ratio is kept.
+    pub fn reset(&mut self) {
+        *self = Camera { pixel_ratio: self.pixel_ratio, ..Camera::new() };
+    }
+
+    /// The board point under a `screen` point, such as where a click landed. Both are in
+    /// CSS pixels so the result can go straight to hit testing.
+    pub fn board_point(&self, screen: Point) -> Point {
+        self.board_from_screen(screen.into()).round()
+    }
+
+    /// Where a `board` point ends up on the screen.
+    pub fn screen_point(&self, board: Point) -> Point {
+        self.screen_from_board(board.into()).round()
+    }
+}
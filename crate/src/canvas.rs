@@ -0,0 +1,145 @@
+//! Drawing onto a HTML5 `<canvas>` from Rust. The whole board is drawn in one call rather
+//! than JS asking for every point, die and dot.
+use std::f64::consts::PI;
+
+use wasm_bindgen::prelude::*;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::camera::Camera;
+use crate::grid::Tessellation;
+use crate::play::Game;
+use crate::render::{self, Command, Paint};
+use crate::theme::Theme;
+
+#[wasm_bindgen]
+pub struct CanvasRenderer {
+    context: CanvasRenderingContext2d,
+    theme: Theme,
+    camera: Camera,
+}
+
+impl CanvasRenderer {
+    /// Draw from here on through the camera.
+    fn look(&self) {
+        let (x, y, scale) = self.camera.backing_transform();
+        let _ = self.context.set_transform(scale, 0_f64, 0_f64, scale, x, y);
+    }
+
+    /// Fill then stroke the path traced so far.
+    fn paint(&self, paint: &Paint) {
+        if let Some(fill) = paint.fill.as_ref() {
+            self.context.set_fill_style(&JsValue::from_str(fill));
+            self.context.fill();
+        }
+        if let Some(stroke) = paint.stroke.as_ref() {
+            let line_width = paint.line_width as f64 * self.camera.pixel_ratio();
+            self.context.set_line_width(line_width);
+            self.context.set_stroke_style(&JsValue::from_str(stroke));
+            self.context.stroke();
+        }
+    }
+
+    fn execute(&self, command: &Command) {
+        self.context.begin_path();
+        match command {
+            Command::Polygon { points, paint } => {
+                if let Some((first, rest)) = points.split_first() {
+                    self.context.move_to(first.x() as f64, first.y() as f64);
+                    rest.iter()
+                        .for_each(|point| {
+                            self.context.line_to(point.x() as f64, point.y() as f64)
+                        });
+                    self.context.close_path();
+                }
+                self.paint(paint);
+            },
+            Command::Rect { top_left, width, height, paint } => {
+                self.context.rect(
+                    top_left.x() as f64, top_left.y() as f64, *width as f64, *height as f64,
+                );
+                self.paint(paint);
+            },
+            Command::Circle { center, radius, paint } => {
+                // Only fails on a negative radius which a `u32` can't be.
+                let _ = self.context.arc(
+                    center.x() as f64, center.y() as f64, *radius as f64, 0_f64, 2_f64 * PI,
+                );
+                self.paint(paint);
+            },
+            Command::Text { position, text, paint } => {
+                self.context.set_text_align("center");
+                self.context.set_text_baseline("middle");
+                let (x, y) = (position.x() as f64, position.y() as f64);
+                if let Some(fill) = paint.fill.as_ref() {
+                    self.context.set_fill_style(&JsValue::from_str(fill));
+                    let _ = self.context.fill_text(text, x, y);
+                }
+                if let Some(stroke) = paint.stroke.as_ref() {
+                    self.context.set_stroke_style(&JsValue::from_str(stroke));
+                    let _ = self.context.stroke_text(text, x, y);
+                }
+            },
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl CanvasRenderer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(context: CanvasRenderingContext2d, theme: Theme) -> Self {
+        CanvasRenderer { context, theme, camera: Camera::new() }
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Draw through a copy of `camera`. Set it again after panning, zooming or a change of
+    /// pixel ratio. Lines are made thicker on high DPI screens to match.
+    pub fn set_camera(&mut self, camera: &Camera) {
+        self.camera = *camera;
+    }
+
+    /// Clear the whole canvas, not just what the camera can see.
+    pub fn clear(&self) {
+        let _ = self.context.set_transform(1_f64, 0_f64, 0_f64, 1_f64, 0_f64, 0_f64);
+        if let Some(canvas) = self.context.canvas() {
+            let (width, height) = (canvas.width() as f64, canvas.height() as f64);
+            self.context.clear_rect(0_f64, 0_f64, width, height);
+        }
+    }
+
+    /// Draw every hex of the `tessellation` along with its dice.
+    pub fn draw(&self, tessellation: &Tessellation) {
+        self.look();
+        render::board(tessellation, &self.theme)
+            .iter()
+            .for_each(|command| self.execute(command));
+        self.context.set_line_width(1_f64);
+    }
+
+    /// Draw only the hexes at `indexes` over what's already there. Pair with
+    /// `Game::dirty_hexes` to redraw just what changed.
+    pub fn draw_hexes(&self, tessellation: &Tessellation, indexes: &[u32]) {
+        self.look();
+        render::hexes(tessellation, indexes, &self.theme)
+            .iter()
+            .for_each(|command| self.execute(command));
+        self.context.set_line_width(1_f64);
+    }
+
+    /// Draw the hexes of `game` that changed since last time and mark them drawn.
+    pub fn draw_dirty(&self, game: &mut Game) {
+        self.draw_hexes(game.current_tessellation(), &game.dirty_hexes());
+        game.clear_dirty();
+    }
+
+    /// Outline the hexes at `indexes` in the highlight colour.
+    pub fn draw_highlights(&self, tessellation: &Tessellation, indexes: &[u32]) {
+        self.look();
+        render::highlights(tessellation, indexes, &self.theme)
+            .iter()
+            .for_each(|command| self.execute(command));
+        self.context.set_line_width(1_f64);
+    }
+}
@@ -1,19 +1,23 @@
 //! Dice drawing
 use std::ops::Neg;
 
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 use crate::primitive::Point;
 
+/// Most dice a hex can hold. There's only room to draw five on a hex.
+pub const MAX_DICE: u8 = 5;
+
 /// Square mostly mirroring the `rect()` method call on HTML5 `<canvas>`.
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Square {
     top_left: Point,
     length: i32,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Dot {
     center: Point,
@@ -26,7 +30,7 @@ impl Dot {
     }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Dot {
     pub fn x(&self) -> i32 {
         self.center.x()
@@ -45,7 +49,7 @@ fn dice_len_from_radius(radius: u32) -> i32 {
     (radius as f64 / 2.5_f64).round() as i32
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Position {
@@ -56,7 +60,45 @@ pub enum Position {
     Center = 4,
 }
 
-#[wasm_bindgen]
+/// Where each die of a stack sits on its hex, first die first.
+pub const STACK: [Position; MAX_DICE as usize] = [
+    Position::TopLeft,
+    Position::TopRight,
+    Position::BottomLeft,
+    Position::BottomRight,
+    Position::Center,
+];
+
+/// Positions of the dice drawn for a stack of `dice`.
+pub fn stack(dice: u8) -> &'static [Position] {
+    &STACK[..dice.min(MAX_DICE) as usize]
+}
+
+/// Pips on the face of a die showing `dots`. Dice show as many dots as their place in the
+/// stack so a hex with three dice shows one, two and three.
+pub fn pips(dots: u8) -> &'static [Position] {
+    match dots {
+        0 => &[],
+        1 => &[Position::Center],
+        2 => &[Position::TopLeft, Position::BottomRight],
+        3 => &[Position::TopLeft, Position::Center, Position::BottomRight],
+        4 => &[
+            Position::TopLeft,
+            Position::TopRight,
+            Position::BottomLeft,
+            Position::BottomRight,
+        ],
+        _ => &[
+            Position::TopLeft,
+            Position::TopRight,
+            Position::BottomLeft,
+            Position::BottomRight,
+            Position::Center,
+        ],
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct DiceTemplate {
     square: Square,
@@ -64,7 +106,7 @@ pub struct DiceTemplate {
 
 /// A dice that sits on the top left from the center. Thus the center is this dice' bottom
 /// right corner.
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl DiceTemplate {
     pub fn new(hex_center: &Point, hex_radius: u32, position: Position) -> Self {
         let len = dice_len_from_radius(hex_radius);
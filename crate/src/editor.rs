@@ -0,0 +1,324 @@
+//! Board editing. Hexes are clicked on, or picked out with the mouse and a key, to change
+//! who owns them, how many dice they hold or whether they can be played on at all. The
+//! result can be played or saved as a map.
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::dice::MAX_DICE;
+use crate::grid::{self, Template, Tessellation};
+use crate::map::{self, Cell, Map, MAX_PLAYERS, NEUTRAL};
+use crate::play::Game;
+use crate::primitive::{Orientation, Point};
+use crate::{failure, jslog, Failure};
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Editor {
+    name: String,
+    author: String,
+    columns: u32,
+    rows: u32,
+    start: Point,
+    radius: u32,
+    orientation: Orientation,
+
+    /// Which of the `MAX_PLAYERS` seats were chosen to play.
+    seats: [bool; MAX_PLAYERS],
+    cells: Vec<Cell>,
+
+    /// Has every hex including the blocked ones so they can still be clicked on.
+    template: Template,
+    tessellation: Tessellation,
+
+    /// Layout pixels per CSS pixel that clicks come in as.
+    pixel_ratio: f64,
+}
+
+impl Editor {
+    /// Start editing `map` for the chosen `seats`, see `seats_from_codes`. The `start`,
+    /// `radius` and `orientation` are as for `grid::generate_template`.
+    pub (crate) fn new(
+        map: &Map,
+        start: Point,
+        radius: u32,
+        orientation: Orientation,
+        seats: [bool; MAX_PLAYERS],
+    ) -> Self {
+        let template = grid::generate_template(
+            map.columns(), map.rows(), start, radius, orientation,
+        );
+        let tessellation = tessellate(&template, map.cells());
+        Editor {
+            name: map.name().to_owned(),
+            author: map.author().to_owned(),
+            columns: map.columns(),
+            rows: map.rows(),
+            start,
+            radius,
+            orientation,
+            seats,
+            cells: map.cells().to_vec(),
+            template,
+            tessellation,
+            pixel_ratio: 1_f64,
+        }
+    }
+
+    /// Every hex owned by the first of the `seats` with a single dice.
+    pub (crate) fn blank(columns: u32, rows: u32, seats: &[bool; MAX_PLAYERS]) -> Map {
+        let owner = first_seat(seats);
+        let cells = vec![Cell::Held { owner, dice: 1 }; (columns * rows) as usize];
+        Map::new("", "", columns, rows, &cells)
+    }
+
+    pub fn map(&self) -> Map {
+        Map::new(&self.name, &self.author, self.columns, self.rows, &self.cells)
+    }
+
+    /// Everything wrong with the board that stops it from being played.
+    pub fn problems(&self) -> Vec<String> {
+        self.problems_for(&self.seats)
+    }
+
+    /// Everything wrong with the board for the chosen `seats`. Every chosen player needs
+    /// territory and no one else can have any.
+    fn problems_for(&self, seats: &[bool; MAX_PLAYERS]) -> Vec<String> {
+        let present = map::present(&self.cells);
+        let mut problems = Vec::new();
+        if seats.iter().filter(|seated| **seated).count() < 2 {
+            problems.push("Choose at least two players.".to_owned());
+        }
+        (1..=MAX_PLAYERS).for_each(|player| match (seats[player - 1], present[player - 1]) {
+            (true, false) => problems.push(format!("Player{} has no territory.", player)),
+            (false, true) => {
+                problems.push(format!("Player{} has territory but isn't playing.", player))
+            },
+            _ => (),
+        });
+
+        self.cells
+            .iter()
+            .enumerate()
+            .for_each(|(index, cell)| if let Cell::Held { dice, .. } = cell {
+                if *dice == 0 || *dice > MAX_DICE {
+                    problems.push(format!(
+                        "Hex {} has {} dice. It must have from 1 to {}.",
+                        index, dice, MAX_DICE,
+                    ));
+                }
+            });
+
+        problems
+    }
+
+    /// Find the hex under the canvas `pixel`, in CSS pixels.
+    fn index_at(&self, pixel: Point) -> Option<usize> {
+        self.tessellation.hex_at(pixel.scale(self.pixel_ratio))
+    }
+
+    /// Change the cell under the `pixel` with `edit`. Returns `true` if a hex was edited.
+    fn edit_with_pixel<F>(&mut self, pixel: Point, edit: F) -> bool
+    where F: FnOnce(Cell, &[bool; MAX_PLAYERS]) -> Cell {
+        let index = match self.index_at(pixel) {
+            Some(index) => index,
+            None => {
+                jslog!("No hexagon under {:?}", &pixel);
+                return false;
+            },
+        };
+
+        self.cells[index] = edit(self.cells[index], &self.seats);
+        self.tessellation = tessellate(&self.template, &self.cells);
+        true
+    }
+}
+
+/// The cell after `cell` when toggling through the kinds of hex.
+fn toggle(cell: Cell, seats: &[bool; MAX_PLAYERS]) -> Cell {
+    match cell {
+        Cell::Held { .. } => Cell::Blocked,
+        Cell::Blocked => Cell::Water,
+        Cell::Water => Cell::Absent,
+        Cell::Absent => Cell::Held { owner: first_seat(seats), dice: 1 },
+    }
+}
+
+/// The lowest numbered of the chosen `seats`, or the first player if none are.
+fn first_seat(seats: &[bool; MAX_PLAYERS]) -> usize {
+    seats.iter().position(|seated| *seated).map(|seat| seat + 1).unwrap_or(1)
+}
+
+/// The chosen seat after `owner`, going back around to the first.
+fn next_seat(seats: &[bool; MAX_PLAYERS], owner: usize) -> usize {
+    (1..=MAX_PLAYERS)
+        .map(|step| (owner + step - 1) % MAX_PLAYERS + 1)
+        .find(|seat| seats[seat - 1])
+        .unwrap_or(owner)
+}
+
+/// Blocked and water cells are still drawn so that they can be clicked on to switch them
+/// back. Absent cells are outlined like blocked ones for the same reason.
+fn tessellate(template: &Template, cells: &[Cell]) -> Tessellation {
+    let holdings = cells
+        .iter()
+        .map(|cell| match cell {
+            Cell::Blocked | Cell::Water | Cell::Absent => (NEUTRAL, 0),
+            Cell::Held { owner, dice } => (*owner, *dice),
+        });
+    let mut tessellation = grid::tessellate(template, holdings);
+
+    cells
+        .iter()
+        .enumerate()
+        .for_each(|(index, cell)| {
+            let hex = tessellation.hex_mut(index).unwrap();
+            match cell {
+                Cell::Blocked | Cell::Absent => hex.block(),
+                Cell::Water => hex.flood(),
+                Cell::Held { .. } => (),
+            }
+        });
+
+    tessellation
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Editor {
+    pub fn tessellation(&self) -> Tessellation {
+        self.tessellation.clone()
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_owned();
+    }
+
+    pub fn set_author(&mut self, author: &str) {
+        self.author = author.to_owned();
+    }
+
+    /// Hand the hex to the next chosen player. Hexes nobody can hold are left alone.
+    pub fn cycle_owner_with_pixel(&mut self, pixel: Point) -> bool {
+        self.edit_with_pixel(pixel, |cell, seats| match cell {
+            Cell::Held { owner, dice } => {
+                Cell::Held { owner: next_seat(seats, owner), dice }
+            },
+            other => other,
+        })
+    }
+
+    /// Add `change` dice to the hex. Kept within one and `MAX_DICE`.
+    pub fn change_dice_with_pixel(&mut self, pixel: Point, change: i8) -> bool {
+        self.edit_with_pixel(pixel, |cell, _| match cell {
+            Cell::Held { owner, dice } => {
+                let dice = (dice as i16 + change as i16).max(1).min(MAX_DICE as i16);
+                Cell::Held { owner, dice: dice as u8 }
+            },
+            other => other,
+        })
+    }
+
+    /// Switch the hex from playable to blocked to water to off the board and back. A hex
+    /// switched back on belongs to the first chosen player with a single dice.
+    pub fn toggle_hex_with_pixel(&mut self, pixel: Point) -> bool {
+        self.edit_with_pixel(pixel, |cell, seats| toggle(cell, seats))
+    }
+
+    /// Choose who plays, coded the same as for `GameSettings::with_players`. Hexes aren't
+    /// changed but `validate` will complain about any held by players that aren't chosen.
+    pub fn choose_players(
+        &mut self, player1_code: u8, player2_code: u8, player3_code: u8, player4_code: u8,
+    ) {
+        self.seats = crate::seats_from_codes(
+            &[player1_code, player2_code, player3_code, player4_code],
+        );
+    }
+
+    /// Problems with the board as `\n` separated lines. Empty when ready to play.
+    pub fn validate(&self) -> String {
+        self.problems().join("\n")
+    }
+
+    /// The board in the hand authored map format.
+    pub fn export_map(&self) -> String {
+        self.map().to_string()
+    }
+
+    /// Play the edited board. Fails if the board doesn't validate for the players chosen
+    /// by the codes, which may have changed since editing started.
+    pub fn start_game(
+        &self,
+        move_limit: u8,
+        player1_code: u8,
+        player2_code: u8,
+        player3_code: u8,
+        player4_code: u8,
+        ai_compute_horizon: usize,
+    ) -> Result<Game, Failure> {
+        let codes = [player1_code, player2_code, player3_code, player4_code];
+        let problems = self.problems_for(&crate::seats_from_codes(&codes));
+        if !problems.is_empty() {
+            return Err(failure(&problems.join("\n")));
+        }
+
+        let mut game = crate::start_map_game(
+            &self.map(),
+            self.start,
+            self.radius,
+            self.orientation,
+            move_limit,
+            &codes,
+            ai_compute_horizon,
+        )?;
+        game.set_pixel_ratio(self.pixel_ratio);
+        Ok(game)
+    }
+
+    /// Same as `Game::set_pixel_ratio`.
+    pub fn set_pixel_ratio(&mut self, pixel_ratio: f64) {
+        self.pixel_ratio = if pixel_ratio > 0_f64 { pixel_ratio } else { 1_f64 };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_against_the_chosen_players() {
+        let map = Map::parse("size: 2x1\nA1 C1\n").unwrap();
+        let seats = crate::seats_from_codes(&[1, 2, 0, 0]);
+        let editor = Editor::new(&map, Point::default(), 10, Orientation::Pointy, seats);
+        assert_eq!(
+            editor.problems(),
+            vec!["Player2 has no territory.", "Player3 has territory but isn't playing."],
+        );
+    }
+
+    #[test]
+    fn owners_cycle_through_the_chosen_players() {
+        let seats = [true, false, true, false];
+        assert_eq!(next_seat(&seats, 1), 3);
+        assert_eq!(next_seat(&seats, 3), 1);
+        assert_eq!(first_seat(&[false, true, true, false]), 2);
+    }
+
+    #[test]
+    fn toggling_goes_through_every_kind_of_hex() {
+        let seats = [false, true, true, false];
+        let held = Cell::Held { owner: 3, dice: 4 };
+        let kinds: Vec<Cell> = (0..4)
+            .scan(held, |cell, _| {
+                *cell = toggle(*cell, &seats);
+                Some(*cell)
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Cell::Blocked,
+                Cell::Water,
+                Cell::Absent,
+                Cell::Held { owner: 2, dice: 1 },
+            ],
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Puzzle generation from AI self-play. The AI plays itself on random boards the same way
+//! `Game::advance` plays. At the start of every turn the position is searched for a way to
+//! eliminate an opponent within the move limit. Positions where exactly one line of
+//! attacks does it are kept as puzzles.
+use std::fmt;
+use std::num::NonZeroU8;
+
+use dicey_dice::{session, game};
+
+use crate::map::{self, MAX_PLAYERS, NEUTRAL};
+use crate::notation::Snapshot;
+use crate::play;
+use crate::puzzle::{Objective, Puzzle, Search};
+
+/// How the self-play games are set up.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub columns: u32,
+    pub rows: u32,
+    pub players: usize,
+    pub move_limit: NonZeroU8,
+    pub ai_compute_horizon: usize,
+
+    /// Number of games to play.
+    pub games: usize,
+
+    /// Games are cut short after this many moves in case they stalemate.
+    pub max_moves: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            columns: 4,
+            rows: 4,
+            players: 2,
+            move_limit: NonZeroU8::new(3).unwrap(),
+            ai_compute_horizon: 3,
+            games: 10,
+            max_moves: 200,
+        }
+    }
+}
+
+/// A puzzle found through self-play. It always has a difficulty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generated {
+    puzzle: Puzzle,
+}
+
+impl Generated {
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    /// From 1 for the easiest up to 5.
+    pub fn difficulty(&self) -> u8 {
+        self.puzzle.difficulty().unwrap_or(1)
+    }
+}
+
+impl fmt::Display for Generated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.puzzle)
+    }
+}
+
+/// Longer solutions are harder, and so are positions with more wrong lines to get lost
+/// in. Every attack of the solution adds one and each doubling of the positions off the
+/// solution adds another. The search goes on past the first solution to check it's the
+/// only one so every wrong line is counted.
+fn estimate_difficulty(search: &Search) -> u8 {
+    let length = search.solutions.first().map(|line| line.len()).unwrap_or(0);
+    let wrong = search.nodes.saturating_sub(length + 1);
+    let lost = ((wrong + 1) as f64).log2().round() as usize;
+    (length + lost).max(1).min(5) as u8
+}
+
+/// Look for a puzzle at the start of the current turn on a `columns` by `rows` board. Only
+/// opponents with no more hexes than the move limit could be wiped out so the others
+/// aren't searched.
+fn find_puzzle(
+    state: &session::State,
+    columns: u32,
+    rows: u32,
+    move_limit: NonZeroU8,
+    name: &str,
+) -> Result<Option<Generated>, String> {
+    let board = state.board();
+    let player = *board.players().current().number();
+    let mut hexes = [0; MAX_PLAYERS + 1];
+    board
+        .grid()
+        .iter()
+        .map(|hex| *hex.data().owner().number())
+        .filter(|owner| *owner != NEUTRAL && *owner <= MAX_PLAYERS)
+        .for_each(|owner| hexes[owner] += 1);
+
+    let snapshot = Snapshot::from_plain_board(board, columns, rows, move_limit);
+    let mut found: Option<Generated> = None;
+
+    for opponent in 1..=MAX_PLAYERS {
+        let count = hexes[opponent];
+        if opponent == player || count == 0 || count > move_limit.get() as usize {
+            continue;
+        }
+
+        let puzzle = Puzzle::new(
+            name, Objective::Eliminate(opponent), move_limit.get(), snapshot.clone(),
+        );
+        let search = puzzle.search(2)?;
+        if search.solutions.len() != 1 {
+            continue;
+        }
+
+        let solution_length = search.solutions[0].len();
+        let difficulty = estimate_difficulty(&search);
+        let puzzle = Puzzle::new(
+            name, Objective::Eliminate(opponent), solution_length as u8, snapshot.clone(),
+        ).with_difficulty(difficulty);
+
+        // Prefer the hardest puzzle the position has to offer.
+        let harder = found
+            .as_ref()
+            .map(|f| difficulty > f.difficulty())
+            .unwrap_or(true);
+        if harder {
+            found = Some(Generated { puzzle });
+        }
+    }
+
+    Ok(found)
+}
+
+/// Play a single self-play game, collecting puzzles along the way.
+fn play_game(settings: &Settings, game_number: usize) -> Result<Vec<Generated>, String> {
+    let present: Vec<bool> = (0..settings.players.max(2).min(MAX_PLAYERS))
+        .map(|_| true)
+        .collect();
+    let board = game::generate_random_board(
+        settings.columns, settings.rows, map::seat_players(&present),
+    );
+    let mut session = session::Setup::new()
+        .set_board(board)
+        .set_move_limit(settings.move_limit)
+        .session()
+        .map_err(|e| format!("Failed to create game session: {}", &e))?;
+
+    let mut found = Vec::new();
+
+    for moves in 0..settings.max_moves {
+        let state = session.current_turn().to_owned();
+        match state.game() {
+            session::Progression::PlayOn(_) => (),
+            _ => break,
+        }
+
+        if state.board().moved() == 0 {
+            let name = format!("Self-play {}.{}", game_number, moves);
+            if let Some(generated) = find_puzzle(
+                &state, settings.columns, settings.rows, settings.move_limit, &name,
+            )? {
+                found.push(generated);
+            }
+        }
+
+        let index = {
+            let scored = session.score_with_depth_horizon(settings.ai_compute_horizon);
+            play::best_choice(scored.choices().iter().enumerate())
+                .ok_or_else(|| "No choices to make".to_owned())?
+        };
+        session.advance(index).map_err(|e| e.to_string())?;
+    }
+
+    Ok(found)
+}
+
+/// Play `settings.games` self-play games and return all the puzzles found.
+pub fn generate(settings: &Settings) -> Result<Vec<Generated>, String> {
+    let mut found = Vec::new();
+    for game_number in 1..=settings.games {
+        found.extend(play_game(settings, game_number)?);
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(position: &str, attacks: u8) -> u8 {
+        let snapshot = Snapshot::parse(position).unwrap();
+        let puzzle = Puzzle::new("Test", Objective::Eliminate(2), attacks, snapshot);
+        estimate_difficulty(&puzzle.search(2).unwrap())
+    }
+
+    #[test]
+    fn longer_puzzles_rate_harder() {
+        let one_attack = rate("A2B1/A1A1_A_0_0_1", 1);
+        let two_attacks = rate("A4B1B2/A1A1A1/A1A1A1_A_0_0_2", 2);
+        assert_eq!(one_attack, 1);
+        assert!(two_attacks > one_attack, "{} and {}", one_attack, two_attacks);
+    }
+
+    #[test]
+    fn wrong_lines_rate_harder() {
+        let search = |nodes| Search { solutions: vec![vec![0, 1]], nodes, depth: 2 };
+        assert_eq!(estimate_difficulty(&search(3)), 2);
+        assert_eq!(estimate_difficulty(&search(10)), 5);
+        assert!(estimate_difficulty(&search(6)) > estimate_difficulty(&search(3)));
+    }
+}
@@ -1,118 +1,475 @@
 //! Grid specific operations
+use std::collections::{HashMap, HashSet};
 
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 use dicey_dice::game::{Holding, Board};
+use dicey_dice::hexagon::{Axial, Cube};
 
-use crate::hex::{Blank, Detail};
-use crate::primitive::Point;
+use crate::hex::{lattice_center, Blank, Detail, LATTICE_CORNERS, PACKED_STRIDE};
+use crate::map::NEUTRAL;
+use crate::primitive::{Orientation, Point, PrecisePoint};
+use crate::render;
+use crate::text;
+use crate::theme::Theme;
+
+/// Outline of a board. Boards are always laid out as a rectangle of hexes and those that
+/// fall outside the shape are taken off the board.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Shape {
+    Rectangle = 0,
+
+    /// As big a hexagon as fits, around the middle hex.
+    Hexagon = 1,
+
+    /// Point up with a hex more on each row down.
+    Triangle = 2,
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Shape::Rectangle
+    }
+}
+
+/// Steps from an axial coordinate to each of the six touching it.
+const DIRECTIONS: [(i32, i32); 6] = [(1, 0), (1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1)];
+
+/// Axial coordinates of the six hexes touching `axial`, whether on a board or not.
+fn axial_neighbours(axial: (i32, i32)) -> impl Iterator<Item = (i32, i32)> {
+    DIRECTIONS.iter().map(move |(dq, dr)| (axial.0 + dq, axial.1 + dr))
+}
+
+/// Cube distance between two axial coordinates, counted in hexes.
+fn axial_distance(a: (i32, i32), b: (i32, i32)) -> i32 {
+    let (dq, dr) = (a.0 - b.0, a.1 - b.1);
+    (dq.abs() + dr.abs() + (dq + dr).abs()) / 2
+}
+
+/// Which hexes of a `columns` by `rows` rectangle make up the `shape`, row by row. Pass
+/// the result to `Template::mask`.
+pub fn shape_mask(shape: Shape, columns: u32, rows: u32) -> Vec<bool> {
+    let cells = (0..(columns * rows) as usize).map(|index| {
+        let (column, row) = (index as u32 % columns, index as u32 / columns);
+        (column as i32, row as i32, axial_from_index(columns, index))
+    });
+    let (columns, rows) = (columns as i32, rows as i32);
+
+    match shape {
+        Shape::Rectangle => cells.map(|_| true).collect(),
+        Shape::Hexagon => {
+            let (column, row) = (columns / 2, rows / 2);
+            let size = column.min(columns - 1 - column).min(row).min(rows - 1 - row);
+            let middle = (column - (row - (row & 1)) / 2, row);
+            cells
+                .map(|(_, _, axial)| axial_distance(axial, middle) <= size)
+                .collect()
+        },
+        Shape::Triangle => {
+            // Only as many rows as there are columns so the bottom row fits. The top is on
+            // an even row so the odd rows are still the ones shunted half a hex right.
+            let height = rows.min(columns);
+            let top = ((rows - height) / 2) & !1;
+            let apex = height / 2 + (columns - height) / 2;
+            cells
+                .map(|(column, row, _)| {
+                    let down = row - top;
+                    let left = apex - down + down / 2;
+                    let right = apex + down / 2;
+                    down >= 0 && down < height && column >= left && column <= right
+                })
+                .collect()
+        },
+    }
+}
 
 /// A drawing template of the grid which will contain all the coordinates precomputed. We
-/// use a rectangular shape in the `Template`. A `Template` is expected to have at least
-/// one hex.
+/// use a rectangular shape in the `Template` though hexes can be taken off it for other
+/// shapes. A `Template` is expected to have at least one hex.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Template {
     columns: u32,
     rows: u32,
     radius: u32,
+    orientation: Orientation,
     hexes: Vec<Blank>,
+
+    /// Axial `(column, row)` coordinate of each hex on the board, in the same order as
+    /// `hexes`.
+    coordinates: Vec<(i32, i32)>,
+
+    /// Index of the hex at each of the `coordinates`.
+    indexes: HashMap<(i32, i32), usize>,
+
+    /// Indexes of hexes that can't be played on.
+    blocked: HashSet<usize>,
+
+    /// Indexes of hexes that aren't on the board at all. They're blocked too.
+    absent: HashSet<usize>,
+
+    /// Indexes of water hexes. They're blocked too but drawn as lakes.
+    water: HashSet<usize>,
 }
 
 impl Template {
-    pub fn new(columns: u32, rows: u32, radius: u32, hexes: &[Blank]) -> Self {
+    /// Each of the `hexes` is paired with its axial coordinate on the board.
+    pub fn new(
+        columns: u32,
+        rows: u32,
+        radius: u32,
+        orientation: Orientation,
+        hexes: &[(Blank, (i32, i32))],
+    ) -> Self {
         Template {
             columns,
             rows,
             radius,
-            hexes: hexes.into_iter().map(|i| *i).collect(),
+            orientation,
+            hexes: hexes.iter().map(|(blank, _)| *blank).collect(),
+            coordinates: hexes.iter().map(|(_, coordinate)| *coordinate).collect(),
+            indexes: hexes
+                .iter()
+                .enumerate()
+                .map(|(index, (_, coordinate))| (*coordinate, index))
+                .collect(),
+            blocked: HashSet::new(),
+            absent: HashSet::new(),
+            water: HashSet::new(),
         }
     }
 
+    /// Axial `(column, row)` coordinate of the hex at `index` on the board.
+    pub fn coordinate(&self, index: usize) -> (i32, i32) {
+        self.coordinates[index]
+    }
+
+    /// Index of the hex at the axial `coordinate`, if there's one there on the board.
+    pub fn index_of(&self, coordinate: (i32, i32)) -> Option<usize> {
+        self.indexes
+            .get(&coordinate)
+            .cloned()
+            .filter(|index| !self.is_absent(*index))
+    }
+
+    /// Indexes of the hexes touching the hex at `index`, found from their coordinates.
+    /// Hexes taken off the board don't touch anything.
+    pub fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        axial_neighbours(self.coordinates[index])
+            .filter_map(move |coordinate| self.index_of(coordinate))
+    }
+
     pub fn radius(&self) -> u32 {
         self.radius
     }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// Mark the hex at `index` as unplayable.
+    pub fn block(&mut self, index: usize) {
+        self.blocked.insert(index);
+    }
+
+    pub fn is_blocked(&self, index: usize) -> bool {
+        self.blocked.contains(&index)
+    }
+
+    /// Take the hex at `index` off the board. It's blocked and not drawn.
+    pub fn remove(&mut self, index: usize) {
+        self.blocked.insert(index);
+        self.absent.insert(index);
+    }
+
+    pub fn is_absent(&self, index: usize) -> bool {
+        self.absent.contains(&index)
+    }
+
+    /// Turn the hex at `index` into water. No one holds it and it can't be crossed.
+    pub fn flood(&mut self, index: usize) {
+        self.blocked.insert(index);
+        self.water.insert(index);
+    }
+
+    pub fn is_water(&self, index: usize) -> bool {
+        self.water.contains(&index)
+    }
+
+    /// Take every hex that's `false` in the `mask` off the board, such as from
+    /// `shape_mask`. The mask is row by row and any hexes past its end are left alone.
+    pub fn mask(&mut self, mask: &[bool]) {
+        mask.iter()
+            .enumerate()
+            .filter(|(_, on_board)| !**on_board)
+            .for_each(|(index, _)| self.remove(index));
+    }
+
+    /// The same board laid out again to fit a `width` by `height` canvas. Blocked, water
+    /// and absent hexes stay that way. See `fit_layout` for the `pixel_ratio`.
+    pub fn refit(
+        &self, width: u32, height: u32, margin: u32, pixel_ratio: f64,
+    ) -> Template {
+        let mut template = fit_template(
+            self.columns, self.rows, width, height, margin, self.orientation, pixel_ratio,
+        );
+        template.blocked = self.blocked.clone();
+        template.absent = self.absent.clone();
+        template.water = self.water.clone();
+        template
+    }
 }
 
-fn generate_row(
-    columns: u32, start: Point, width: u32, radius: u32
-) -> impl Iterator<Item = Blank> {
-    let translate_by = Point::new(width as i32, 0);
-    (0..columns)
-        .map(move |column| {
-            let translate_by = translate_by * column as i32;
-            let start = start + translate_by;
-            Blank::new(&start, radius)
-        })
+/// Space left around a board fitted to the canvas when no margin is asked for.
+pub const FIT_MARGIN: u32 = 8;
+
+/// Where the first hex goes and how big the hexes are so a board fits a canvas.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Fit {
+    start: Point,
+    radius: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Fit {
+    /// Center of the top left hex. Pass to `generate_template` as the `start`.
+    pub fn start(&self) -> Point {
+        self.start
+    }
+
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+}
+
+/// The largest hexes that fit a `columns` by `rows` board in a `width` by `height` canvas
+/// with `margin` pixels to spare all round, and where the top left hex goes so the board
+/// sits in the middle. The radius is never less than one however small the canvas.
+///
+/// The canvas and margin are in CSS pixels while the layout is for the backing store,
+/// `pixel_ratio` times bigger, so high DPI screens get crisp edges.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn fit_layout(
+    columns: u32,
+    rows: u32,
+    width: u32,
+    height: u32,
+    margin: u32,
+    orientation: Orientation,
+    pixel_ratio: f64,
+) -> Fit {
+    let pixel_ratio = if pixel_ratio > 0_f64 { pixel_ratio } else { 1_f64 };
+
+    // Worked out for pointy hexes on a canvas flipped the same way as the flat layout.
+    let (width, height) = match orientation {
+        Orientation::Pointy => (width as f64, height as f64),
+        Orientation::Flat => (height as f64, width as f64),
+    };
+    let (width, height) = (width * pixel_ratio, height * pixel_ratio);
+    let margin = margin as f64 * pixel_ratio;
+    let room_width = (width - 2_f64 * margin).max(0_f64);
+    let room_height = (height - 2_f64 * margin).max(0_f64);
+
+    // The board measured in the half widths and quarter heights of the lattice. Odd rows
+    // stick out half a hex to the right.
+    let across = (2 * columns + if rows > 1 { 1 } else { 0 }) as f64;
+    let down = (3 * rows + 1) as f64;
+    let half_width = 3_f64.sqrt() / 2_f64;
+    let quarter_height = 1_f64 / 2_f64;
+
+    let radius = (room_width / (across * half_width))
+        .min(room_height / (down * quarter_height))
+        .floor()
+        .max(1_f64);
+    let board_width = across * half_width * radius;
+    let board_height = down * quarter_height * radius;
+    let start = PrecisePoint::new(
+        (width - board_width) / 2_f64 + half_width * radius,
+        (height - board_height) / 2_f64 + radius,
+    );
+
+    Fit { start: start.orient(orientation).round(), radius: radius as u32 }
+}
+
+/// A template for a `columns` by `rows` board with the largest hexes that fit a `width`
+/// by `height` canvas, centered. See `fit_layout`.
+pub fn fit_template(
+    columns: u32,
+    rows: u32,
+    width: u32,
+    height: u32,
+    margin: u32,
+    orientation: Orientation,
+    pixel_ratio: f64,
+) -> Template {
+    let fit = fit_layout(columns, rows, width, height, margin, orientation, pixel_ratio);
+    generate_template(columns, rows, fit.start, fit.radius, orientation)
 }
 
 /// Generate a new template. Begins from `start` as the top left and works its way right
 /// and down. There is implicit spacing between the hexes as a circle radius is used to
 /// define their size. Thus the circle edges (which aren't drawn) will touch but the hex
 /// edges which are within the circle (except for the corners that touch) won't touch.
-pub fn generate_template(columns: u32, rows: u32, start: Point, radius: u32) -> Template {
-    let width = (3_f64.sqrt() * radius as f64).round() as u32;
-    let half_width = (width / 2) as i32;
-    let height = 2 * radius;
-    let three_quarter_height = (height as f64 * 0.75_f64).round() as i32;
-    let one_and_half_height = (height as f64 * 1.5_f64).round() as i32;
-
-    let even_row_start = Point::new(0, 0);
-    let odd_row_start = Point::new(half_width, three_quarter_height);
-    //let row_increment = Point::new(1, one_and_half_height);
-    
-    let mut blanks: Vec<Blank> = Vec::new();
-    let mut even_rows = 0;
-    let mut odd_rows = 0;
-    
-    (0..rows)
-        .for_each(|row| {
-            let offset = if row % 2 == 0 {
-                // We have an even row
-                let height_increment = one_and_half_height * even_rows;
-                even_rows += 1;
-                even_row_start + Point::new(0, height_increment)
-            } else {
-                // We have an odd row
-                let height_increment = one_and_half_height * odd_rows;
-                odd_rows += 1;
-                odd_row_start + Point::new(0, height_increment)
+///
+/// Every center and corner is placed on the lattice from `hex::lattice_center` and only
+/// rounded to a pixel at the end. That way hexes next to each other share exactly the
+/// same corners and there are no hairline gaps or overlaps at small radii.
+///
+/// The layout is worked out for pointy hexes and then flipped around `start` for flat
+/// ones. The hexes are stored in the same order either way.
+pub fn generate_template(
+    columns: u32, rows: u32, start: Point, radius: u32, orientation: Orientation,
+) -> Template {
+    let start = PrecisePoint::from(start);
+    let half_width = 3_f64.sqrt() * radius as f64 / 2_f64;
+    let quarter_height = radius as f64 / 2_f64;
+    let to_pixel = |(across, down): (i32, i32)| {
+        let offset = PrecisePoint::new(
+            across as f64 * half_width, down as f64 * quarter_height,
+        );
+        (start + offset.orient(orientation)).round()
+    };
+
+    let blanks: Vec<(Blank, (i32, i32))> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .map(|(column, row)| {
+            let (across, down) = lattice_center(column, row);
+            let corner = |index: usize| {
+                let (corner_across, corner_down) = LATTICE_CORNERS[index];
+                to_pixel((across + corner_across, down + corner_down))
             };
-            
-            // Generate each line from the start point.
-            blanks.extend(generate_row(columns, offset + start, width, radius));
-        });
+            let points = [corner(0), corner(1), corner(2), corner(3), corner(4), corner(5)];
+            let blank = Blank::from_points(to_pixel((across, down)), points);
+            (blank, axial_from_offset(column, row))
+        })
+        .collect();
 
-    Template::new(rows, columns, radius, blanks.as_slice())
+    Template::new(columns, rows, radius, orientation, &blanks)
+}
+
+/// Indexes of the hexes touching the hex at `index` of a whole `columns` by `rows`
+/// rectangle laid out by `generate_template`. Boards with hexes taken off should ask
+/// `Template::neighbours` instead.
+pub fn neighbours(columns: u32, rows: u32, index: usize) -> impl Iterator<Item = usize> {
+    axial_neighbours(axial_from_index(columns, index))
+        .filter_map(move |axial| index_from_axial(columns, rows, axial))
+}
+
+/// Index of the hex at the axial `(column, row)` coordinate from `Point::axial_parts` if
+/// it's on a `columns` by `rows` board.
+pub fn index_from_axial(columns: u32, rows: u32, axial: (i32, i32)) -> Option<usize> {
+    let (q, r) = axial;
+    let row = r;
+    let column = q + (r - (r & 1)) / 2;
+    if column >= 0 && column < columns as i32 && row >= 0 && row < rows as i32 {
+        Some((row * columns as i32 + column) as usize)
+    } else {
+        None
+    }
+}
+
+/// Axial `(column, row)` coordinate of the hex at `index` on a board `columns` wide. The
+/// reverse of `index_from_axial`.
+pub fn axial_from_index(columns: u32, index: usize) -> (i32, i32) {
+    axial_from_offset(index as u32 % columns, index as u32 / columns)
+}
+
+/// Axial coordinate of the hex at `column` and `row` where odd rows are shunted half a
+/// hex to the right.
+fn axial_from_offset(column: u32, row: u32) -> (i32, i32) {
+    let (column, row) = (column as i32, row as i32);
+    (column - (row - (row & 1)) / 2, row)
 }
-    
 
 /// A grid meant to be viewable. Can also find the hexagon within which a point collides
 /// with to help with selecting. A `Tessellation` is expected to have at least one hex.
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Tessellation {
     rows: u32,
     columns: u32,
     radius: u32,
+    orientation: Orientation,
     hexes: Vec<Detail>,
+
+    /// Filled by `pack` for JS to read straight out of wasm memory.
+    packed: Vec<i32>,
 }
 
 impl Tessellation {
-    pub fn new(rows: u32, columns: u32, radius: u32, hexes: &[Detail]) -> Self {
+    pub fn new(
+        rows: u32, columns: u32, radius: u32, orientation: Orientation, hexes: &[Detail],
+    ) -> Self {
         Tessellation {
             rows,
             columns,
             radius,
+            orientation,
             hexes: hexes.into_iter().map(|i| *i).collect(),
+            packed: Vec::new(),
         }
     }
 
     pub fn hex_mut(&mut self, index: usize) -> Option<&mut Detail> {
         self.hexes.get_mut(index)
     }
+
+    /// Top left and bottom right corners of a box around every hex outline on the board.
+    pub fn bounds(&self) -> (Point, Point) {
+        let mut present = self.hexes.iter().filter(|detail| !detail.absent()).peekable();
+        let first = present
+            .peek()
+            .map(|detail| detail.center())
+            .unwrap_or_else(|| self.start_hex_center());
+        let corners = present
+            .flat_map(|detail| (0..6).map(move |corner| detail.point(corner)));
+        corners.fold((first, first), |(top_left, bottom_right), point| (
+            Point::new(top_left.x().min(point.x()), top_left.y().min(point.y())),
+            Point::new(bottom_right.x().max(point.x()), bottom_right.y().max(point.y())),
+        ))
+    }
+
+    /// Index of the hex under `point`, a point on the canvas rather than one relative to
+    /// the board. Points in the gaps around the edge of the board are `None` even when
+    /// they'd round to a hex just off it. The point is in the same pixels as the layout so
+    /// CSS pixels need scaling up to the backing store first.
+    pub fn hex_at(&self, point: Point) -> Option<usize> {
+        let relative = PrecisePoint::from(point) - self.start_hex_center().into();
+        let axial = relative.axial_parts(self.radius as f64, self.orientation);
+        let index = index_from_axial(self.columns, self.rows, axial);
+
+        // The outlines are snapped to whole pixels so a point right on the edge can round
+        // to the hex next door. Check the nearest hex first then its neighbours.
+        let nearest = match index {
+            Some(index) => index,
+            None => {
+                let (q, r) = axial;
+                let row = r.max(0).min(self.rows as i32 - 1);
+                let column = (q + (r - (r & 1)) / 2).max(0).min(self.columns as i32 - 1);
+                (row * self.columns as i32 + column) as usize
+            },
+        };
+        std::iter::once(nearest)
+            .chain(neighbours(self.columns, self.rows, nearest))
+            .find(|index| {
+                let detail = &self.hexes[*index];
+                !detail.absent() && detail.contains(point)
+            })
+    }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Tessellation {
     /// If index is out of bounds will wrap around to the beginning.
     pub fn hex(&self, index: usize) -> Detail {
@@ -124,36 +481,322 @@ impl Tessellation {
         self.hexes.len()
     }
 
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
     pub fn radius(&self) -> u32 {
         self.radius
     }
 
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Pack every hex into one buffer, `PACKED_STRIDE` numbers per hex. JS can then view
+    /// it with `new Int32Array(memory.buffer, t.packed_ptr(), t.packed_len())` instead of
+    /// asking for each hex and point. Pack again after the tessellation changes and make
+    /// a new view since wasm memory may have moved.
+    pub fn pack(&mut self) {
+        let mut packed = std::mem::replace(&mut self.packed, Vec::new());
+        packed.clear();
+        packed.reserve(self.hexes.len() * PACKED_STRIDE);
+        self.hexes.iter().for_each(|hex| hex.pack(&mut packed));
+        self.packed = packed;
+    }
+
+    pub fn packed_ptr(&self) -> *const i32 {
+        self.packed.as_ptr()
+    }
+
+    /// Number of `i32`s in the packed buffer. Zero until `pack` is called.
+    pub fn packed_len(&self) -> usize {
+        self.packed.len()
+    }
+
     pub fn start_hex_center(&self) -> Point {
         self.hexes
             .first()
             .map(|d| d.center())
             .unwrap_or_default()
     }    
+
+    /// Text diagram of the board for logs and bug reports. Each hex is its owner letter and
+    /// dice, `A3`, with the column and row numbers around the edge. The attacking hex is
+    /// marked with `*`, the hexes it threatens with `!`, blocked hexes are `##`, water is
+    /// `~~` and those not on the board are left blank. Odd rows are shunted half a hex to
+    /// the right just like the drawn board.
+    pub fn to_text(&self) -> String {
+        text::render(&render::diagram(self, &Theme::default()))
+    }
+}
+
+/// Owner and dice of each hex of the `template`, matched to the `board` by coordinate. Any
+/// the board doesn't have are left to the `NEUTRAL` player with no dice.
+pub (crate) fn holdings(template: &Template, board: &Board) -> Vec<(usize, u8)> {
+    let grid = board.grid();
+    let holdings: Vec<(usize, u8)> = grid
+        .iter()
+        .map(|hex| (*hex.data().owner().number(), hex.data().dice()))
+        .collect();
+    template.coordinates
+        .iter()
+        .map(|coordinate| {
+            let cube: Cube = Axial::from(*coordinate).into();
+            grid.fetch_index(cube)
+                .ok()
+                .and_then(|index| holdings.get(index).cloned())
+                .unwrap_or((NEUTRAL, 0))
+        })
+        .collect()
 }
 
-/// Produce a new `Tessellation` by merging a `Template` and `Board`. The dimensions of the
-/// `Board` must match the `Template` otherwise things will go awry.
+/// Produce a new `Tessellation` by merging a `Template` and `Board`. See `holdings`.
 pub (crate) fn generate_tessellation(template: &Template, board: &Board) -> Tessellation {
+    tessellate(template, holdings(template, board).into_iter())
+}
+
+/// Bring the owners and dice of a `Tessellation` generated from `template` up to date with
+/// `board`. The layout and danger of every hex is left alone and only the hexes that
+/// changed are touched. Their indexes are returned in order.
+pub (crate) fn update_tessellation(
+    tessellation: &mut Tessellation, template: &Template, board: &Board,
+) -> Vec<usize> {
+    let mut changed = Vec::new();
+    for (index, (owner, dice)) in holdings(template, board).into_iter().enumerate() {
+        if tessellation.hexes[index].hold(owner, dice) {
+            changed.push(index);
+        }
+    }
+    changed
+}
+
+/// Produce a new `Tessellation` from a `Template` and the owner and dice of each hex in
+/// the same order as the `Template`.
+pub (crate) fn tessellate<I>(template: &Template, holdings: I) -> Tessellation
+where I: Iterator<Item = (usize, u8)> {
     let detail: Vec<Detail> = template.hexes
         .iter()
-        .zip(board.grid().iter())
-        .map(|(blank, hex)| Detail::new(
-            blank.points(),
-            blank.center(),
-            *hex.data().owner().number(),
-            hex.data().dice(),
-        ))
+        .zip(holdings)
+        .enumerate()
+        .map(|(index, (blank, (owner, dice)))| {
+            let mut detail = Detail::new(blank.points(), blank.center(), owner, dice);
+            if template.is_absent(index) {
+                detail.remove();
+            } else if template.is_water(index) {
+                detail.flood();
+            } else if template.is_blocked(index) {
+                detail.block();
+            }
+            detail
+        })
         .collect();
 
     Tessellation {
         rows: template.rows,
         columns: template.columns,
         radius: template.radius,
+        orientation: template.orientation,
         hexes: detail,
+        packed: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::play::Game;
+
+    const CROSSING: &str = "A2B1A3/B2#A1/A1A1B1_A_0_0_3";
+
+    fn crossing() -> Game {
+        crate::start_game_from_position(
+            CROSSING, Point::default(), 10, Orientation::Pointy, 1, 1, 0, 0, 1,
+        ).unwrap()
+    }
+
+    const ORIENTATIONS: [Orientation; 2] = [Orientation::Pointy, Orientation::Flat];
+
+    #[test]
+    fn neighbours_share_two_corners() {
+        for orientation in ORIENTATIONS.iter() {
+            for radius in 1..=8 {
+                let template = generate_template(
+                    6, 5, Point::new(13, 11), radius, *orientation,
+                );
+                for index in 0..template.hexes.len() {
+                    let corners = template.hexes[index].points();
+                    for next in neighbours(6, 5, index) {
+                        let shared = template.hexes[next]
+                            .points()
+                            .iter()
+                            .filter(|point| corners.contains(point))
+                            .count();
+                        // A hex of radius one is under two pixels across so other corners
+                        // can round onto the same pixel as well.
+                        let expected = if radius == 1 { shared.max(2) } else { 2 };
+                        assert_eq!(
+                            shared, expected,
+                            "hexes {} and {} of radius {} {:?}",
+                            index, next, radius, orientation,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every layout `hex_at` is checked against, boards with hexes taken off included.
+    fn layouts() -> Vec<Tessellation> {
+        let mut layouts = Vec::new();
+        for (columns, rows) in [(3, 3), (6, 5), (7, 4), (8, 8)].iter().cloned() {
+            for radius in [3, 10, 25].iter().cloned() {
+                for orientation in ORIENTATIONS.iter().cloned() {
+                    for shape in [Shape::Rectangle, Shape::Hexagon].iter().cloned() {
+                        let start = Point::new(radius as i32 * 2, radius as i32 * 2);
+                        let mut template = generate_template(
+                            columns, rows, start, radius, orientation,
+                        );
+                        template.mask(&shape_mask(shape, columns, rows));
+                        layouts.push(tessellate(&template, std::iter::repeat((1, 1))));
+                    }
+                }
+            }
+        }
+        layouts
+    }
+
+    #[test]
+    fn finds_the_hex_at_every_center() {
+        for tessellation in layouts() {
+            for index in 0..tessellation.len() {
+                let detail = tessellation.hex(index);
+                let expected = if detail.absent() { None } else { Some(index) };
+                assert_eq!(tessellation.hex_at(detail.center()), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn finds_nothing_outside_the_bounds() {
+        for tessellation in layouts() {
+            let (top_left, bottom_right) = tessellation.bounds();
+            let (left, right) = (top_left.x() - 1, bottom_right.x() + 1);
+            let (top, bottom) = (top_left.y() - 1, bottom_right.y() + 1);
+            let sides = (top..=bottom)
+                .flat_map(|y| vec![Point::new(left, y), Point::new(right, y)]);
+            let ends = (left..=right)
+                .flat_map(|x| vec![Point::new(x, top), Point::new(x, bottom)]);
+            let edges = sides.chain(ends);
+            for point in edges {
+                assert_eq!(tessellation.hex_at(point), None, "{:?}", point);
+            }
+        }
+    }
+
+    #[test]
+    fn never_finds_absent_hexes() {
+        for tessellation in layouts() {
+            let (top_left, bottom_right) = tessellation.bounds();
+            for y in top_left.y()..=bottom_right.y() {
+                for x in top_left.x()..=bottom_right.x() {
+                    if let Some(index) = tessellation.hex_at(Point::new(x, y)) {
+                        let detail = tessellation.hex(index);
+                        assert!(!detail.absent(), "{} at {}, {}", index, x, y);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Hexes in each row of a `mask` that has any, along with the sum of the leftmost and
+    /// rightmost of them counted in half hexes across.
+    fn rows_of(mask: &[bool], columns: u32) -> Vec<(usize, i32)> {
+        mask.chunks(columns as usize)
+            .enumerate()
+            .filter(|(_, row)| row.contains(&true))
+            .map(|(row, hexes)| {
+                let across: Vec<i32> = (0..hexes.len())
+                    .filter(|column| hexes[*column])
+                    .map(|column| 2 * column as i32 + (row & 1) as i32)
+                    .collect();
+                (across.len(), across[0] + across[across.len() - 1])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hexagon_masks_are_whole_and_symmetric() {
+        for (columns, rows, size) in [(7, 7, 3), (5, 5, 2), (8, 8, 3), (9, 5, 2)].iter() {
+            let mask = shape_mask(Shape::Hexagon, *columns, *rows);
+            assert_eq!(mask.iter().filter(|on| **on).count(), 1 + 3 * size * (size + 1));
+
+            let rows = rows_of(&mask, *columns);
+            let widths: Vec<usize> = rows.iter().map(|(width, _)| *width).collect();
+            assert_eq!(widths.iter().rev().cloned().collect::<Vec<_>>(), widths);
+            assert!(rows.iter().all(|(_, middle)| *middle == rows[0].1));
+        }
+    }
+
+    #[test]
+    fn neighbours_are_found_from_coordinates() {
+        let mut template = generate_template(
+            7, 7, Point::default(), 10, Orientation::Pointy,
+        );
+        template.mask(&shape_mask(Shape::Hexagon, 7, 7));
+
+        let mut counts = [0; 7];
+        for index in (0..49).filter(|index| !template.is_absent(*index)) {
+            let touching: Vec<usize> = template.neighbours(index).collect();
+            for next in touching.iter().cloned() {
+                assert!(!template.is_absent(next), "{} touches absent {}", index, next);
+                let distance = axial_distance(
+                    template.coordinate(index), template.coordinate(next),
+                );
+                assert_eq!(distance, 1, "{} and {}", index, next);
+                assert!(template.neighbours(next).any(|back| back == index));
+            }
+            counts[touching.len()] += 1;
+        }
+        // A hexagon of size three has six corners, twelve more along its sides and the
+        // rest inside.
+        assert_eq!(counts, [0, 0, 0, 6, 12, 0, 19]);
+        assert!(template.index_of(template.coordinate(0)).is_none());
+    }
+
+    #[test]
+    fn triangle_masks_are_whole_and_symmetric() {
+        for (columns, rows) in [(6, 6), (5, 5), (8, 4), (4, 8), (7, 7)].iter() {
+            let mask = shape_mask(Shape::Triangle, *columns, *rows);
+            let height = *columns.min(rows) as usize;
+            assert_eq!(mask.iter().filter(|on| **on).count(), height * (height + 1) / 2);
+
+            let rows = rows_of(&mask, *columns);
+            let widths: Vec<usize> = rows.iter().map(|(width, _)| *width).collect();
+            assert_eq!(widths, (1..=height).collect::<Vec<_>>());
+            assert!(rows.iter().all(|(_, middle)| *middle == rows[0].1));
+        }
+    }
+
+    #[test]
+    fn draws_the_board_as_text() {
+        let text = crossing().tessellation().to_text();
+        assert_eq!(text, include_str!("../tests/golden/crossing.txt"));
+    }
+
+    #[test]
+    fn marks_the_selection_in_text() {
+        let mut game = crossing();
+        assert!(!game.select_hex_with_index(2));
+        let text = game.tessellation().to_text();
+        assert_eq!(text, include_str!("../tests/golden/crossing_selected.txt"));
+
+        assert!(!game.select_hex_with_index(2));
+        let text = game.tessellation().to_text();
+        assert_eq!(text, include_str!("../tests/golden/crossing.txt"));
     }
 }
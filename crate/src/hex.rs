@@ -1,11 +1,12 @@
 //! Hexagon operations
 
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-use crate::primitive::{Colour, Colours, Point};
+use crate::primitive::{Colour, Colours, Orientation, Point, PrecisePoint};
 
 /// Determine a partical hexagon point/corner. Numbers higher than 6 wrap around.
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn pointy_hex_corner(center: &Point, radius: u32, corner: u8) -> Point {    
     let corner: f64 = corner.into();
     let radius: f64 = radius.into();
@@ -15,7 +16,41 @@ pub fn pointy_hex_corner(center: &Point, radius: u32, corner: u8) -> Point {
     let new_x = (center.x() as f64) + radius * radians.cos();
     let new_y = (center.y() as f64) + radius * radians.sin();
 
-    Point::new(new_x as i32, new_y as i32)
+    PrecisePoint::new(new_x, new_y).round()
+}
+
+/// The pointy corners in the same order as `pointy_hex_corner` from 1 to 6, measured
+/// from the center in half hex widths across and quarter hex heights down. Every corner
+/// and center of a pointy layout sits on this lattice so neighbouring hexes work out
+/// their shared corners from the same numbers.
+pub const LATTICE_CORNERS: [(i32, i32); 6] = [
+    (1, 1),
+    (0, 2),
+    (-1, 1),
+    (-1, -1),
+    (0, -2),
+    (1, -1),
+];
+
+/// Center of the hex at `column` and `row` of a pointy layout on the same lattice as
+/// `LATTICE_CORNERS`, measured from the center of the first hex. Odd rows are shunted
+/// half a hex to the right.
+pub fn lattice_center(column: u32, row: u32) -> (i32, i32) {
+    (2 * column as i32 + (row & 1) as i32, 3 * row as i32)
+}
+
+/// Corner of a hexagon in either `orientation`. Numbers higher than 6 wrap around. Flat
+/// corners are the pointy ones flipped the same way as the layout so they're listed in
+/// the opposite direction.
+pub fn hex_corner(
+    center: &Point, radius: u32, corner: u8, orientation: Orientation,
+) -> Point {
+    match orientation {
+        Orientation::Pointy => pointy_hex_corner(center, radius, corner),
+        Orientation::Flat => {
+            pointy_hex_corner(&center.transpose(), radius, corner).transpose()
+        },
+    }
 }
 
 /// Blank hexagon. Used for templating.
@@ -26,20 +61,25 @@ pub struct Blank {
 }
 
 impl Blank {
-    pub fn new(center: &Point, radius: u32) -> Self {
+    pub fn new(center: &Point, radius: u32, orientation: Orientation) -> Self {
         Blank {
             points: [
-                pointy_hex_corner(center, radius, 1),
-                pointy_hex_corner(center, radius, 2),
-                pointy_hex_corner(center, radius, 3),
-                pointy_hex_corner(center, radius, 4),
-                pointy_hex_corner(center, radius, 5),
-                pointy_hex_corner(center, radius, 6),
+                hex_corner(center, radius, 1, orientation),
+                hex_corner(center, radius, 2, orientation),
+                hex_corner(center, radius, 3, orientation),
+                hex_corner(center, radius, 4, orientation),
+                hex_corner(center, radius, 5, orientation),
+                hex_corner(center, radius, 6, orientation),
             ],
             center: *center,
         }
     }
     
+    /// Hexagon from corners that have already been worked out.
+    pub fn from_points(center: Point, points: [Point; 6]) -> Self {
+        Blank { points, center }
+    }
+
     pub fn points(&self) -> &[Point] {
         &self.points
     }
@@ -49,6 +89,17 @@ impl Blank {
     }
 }
 
+/// Number of `i32`s each hex takes up in a packed render buffer. In order they are the
+/// x and y of the six corners, the x and y of the center, the `Colour` to draw with, the
+/// player number, the dice and the `FLAG_` bits.
+pub const PACKED_STRIDE: usize = 18;
+
+pub const FLAG_ATTACKING: i32 = 1;
+pub const FLAG_THREATENED: i32 = 1 << 1;
+pub const FLAG_BLOCKED: i32 = 1 << 2;
+pub const FLAG_ABSENT: i32 = 1 << 3;
+pub const FLAG_WATER: i32 = 1 << 4;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum Danger {
     Safe,
@@ -56,8 +107,23 @@ enum Danger {
     Threatened,
 }   
 
+/// What sort of ground a hex is. Only land can be held by a player.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Kind {
+    Land,
+
+    /// Unplayable and only outlined.
+    Blocked,
+
+    /// Impassable terrain. Drawn in its own colour but nobody can hold or attack it.
+    Water,
+
+    /// Not on the board at all.
+    Absent,
+}
+
 /// A hexagon tile with all the information needed to render it to a HTML5 canvas.
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Detail {
     points: [Point; 6],
@@ -66,6 +132,7 @@ pub struct Detail {
     player_number: usize,
     danger: Danger,
     dice: u8,
+    kind: Kind,
 }
 
 impl Detail {
@@ -84,11 +151,70 @@ impl Detail {
             player_number,
             danger: Danger::Safe,
             dice,
+            kind: Kind::Land,
+        }
+    }
+
+    /// Hand this hex over to `player_number` with `dice`. Whether that changed anything.
+    pub fn hold(&mut self, player_number: usize, dice: u8) -> bool {
+        if self.player_number == player_number && self.dice == dice {
+            return false;
+        }
+        self.colours = Colours::from_player_number(player_number);
+        self.player_number = player_number;
+        self.dice = dice;
+        true
+    }
+
+    /// Make this hex unplayable. It'll be skipped when drawing.
+    pub fn block(&mut self) {
+        self.kind = Kind::Blocked;
+    }
+
+    /// Turn this hex into water. It's blocked but still drawn, in the water colour.
+    pub fn flood(&mut self) {
+        self.kind = Kind::Water;
+    }
+
+    /// Take this hex off the board altogether, for boards that aren't rectangles. It's
+    /// blocked, not drawn at all and can't be clicked on.
+    pub fn remove(&mut self) {
+        self.kind = Kind::Absent;
+    }
+
+    /// Append this hex to a packed render buffer. See `PACKED_STRIDE` for the layout.
+    pub fn pack(&self, buffer: &mut Vec<i32>) {
+        self.points
+            .iter()
+            .for_each(|point| buffer.extend_from_slice(&[point.x(), point.y()]));
+        let mut flags = 0;
+        if self.danger == Danger::Attacking {
+            flags |= FLAG_ATTACKING;
+        }
+        if self.danger == Danger::Threatened {
+            flags |= FLAG_THREATENED;
+        }
+        if self.blocked() {
+            flags |= FLAG_BLOCKED;
         }
+        if self.kind == Kind::Absent {
+            flags |= FLAG_ABSENT;
+        }
+        if self.kind == Kind::Water {
+            flags |= FLAG_WATER;
+        }
+        buffer.extend_from_slice(&[
+            self.center.x(),
+            self.center.y(),
+            self.colour() as i32,
+            self.player_number as i32,
+            self.dice as i32,
+            flags,
+        ]);
     }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Detail {
     pub fn center(&self) -> Point {
         self.center
@@ -111,6 +237,22 @@ impl Detail {
         self.danger = Danger::Threatened;
     }
 
+    /// Whether `point` is inside the outline of this hex or on its edge.
+    pub fn contains(&self, point: Point) -> bool {
+        let sides: Vec<i64> = (0..6)
+            .map(|corner| {
+                let (from, to) = (self.point(corner), self.point(corner + 1));
+                let edge = (to.x() - from.x(), to.y() - from.y());
+                let offset = (point.x() - from.x(), point.y() - from.y());
+                edge.0 as i64 * offset.1 as i64 - edge.1 as i64 * offset.0 as i64
+            })
+            .collect();
+
+        // Inside a convex shape the point is on the same side of every edge, whichever way
+        // round the corners go.
+        sides.iter().all(|side| *side >= 0) || sides.iter().all(|side| *side <= 0)
+    }
+
     /// If index is out of bounds, will wrap around to the beginning, like going around a
     /// circle. Of course, a hexagon has six sides so the indexes will be from 0 to 5.
     pub fn point(&self, index: usize) -> Point {
@@ -131,6 +273,9 @@ impl Detail {
     }
 
     pub fn colour(&self) -> Colour {
+        if self.kind == Kind::Water {
+            return Colour::SteelBlue;
+        }
         match self.danger {
             Danger::Safe => self.colours.unselected_colour,
             Danger::Attacking => self.colours.selected_colour,
@@ -141,4 +286,31 @@ impl Detail {
     pub fn dice(&self) -> u8 {
         self.dice
     }
+
+    pub fn player_number(&self) -> usize {
+        self.player_number
+    }
+
+    pub fn attacking(&self) -> bool {
+        self.danger == Danger::Attacking
+    }
+
+    pub fn threatened(&self) -> bool {
+        self.danger == Danger::Threatened
+    }
+
+    /// Anything other than land. Nobody can hold or attack a blocked hex.
+    pub fn blocked(&self) -> bool {
+        self.kind != Kind::Land
+    }
+
+    /// Water is blocked too.
+    pub fn water(&self) -> bool {
+        self.kind == Kind::Water
+    }
+
+    /// Not part of the board. Absent hexes are always blocked too.
+    pub fn absent(&self) -> bool {
+        self.kind == Kind::Absent
+    }
 }
@@ -1,6 +1,7 @@
 use std::num::NonZeroU8;
 use std::collections::HashSet;
 
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 use dicey_dice::{session, game};
@@ -11,39 +12,83 @@ pub mod dice;
 pub mod grid;
 pub mod primitive;
 pub mod play;
+pub mod analysis;
+pub mod map;
+pub mod notation;
+pub mod editor;
+pub mod puzzle;
+pub mod generator;
+pub mod tutorial;
+pub mod tournament;
+pub mod rng;
+pub mod render;
+pub mod svg;
+pub mod text;
+pub mod theme;
+pub mod camera;
+pub mod terrain;
+#[cfg(feature = "wasm")]
+pub mod canvas;
+#[cfg(feature = "wasm")]
+pub mod webgl;
 
-pub use self::primitive::Point;
-pub use self::grid::Tessellation;
+pub use self::primitive::{Orientation, Point};
+pub use self::grid::{Shape, Tessellation};
+pub use self::camera::Camera;
 pub use self::dice::{DiceTemplate, Position};
 
 /// Re-export for debug purposes.
 pub use self::hex::pointy_hex_corner;
 
-#[wasm_bindgen]
-extern {
-    fn alert(s: &str);
+pub use self::utils::Failure;
+use self::utils::failure;
+
+/// Logs through the `log` facade. In the browser `init` routes it to the JS console while
+/// native builds can install whichever logger they like.
+#[macro_export]
+macro_rules! jslog {
+    ($($t:tt)*) => (::log::info!($($t)*))
 }
 
-#[wasm_bindgen]
-extern {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(msg: &str);
+/// Pick out the players coded as AI. A code of `2` is AI.
+fn ai_players_from_codes(player_codes: &[u8]) -> HashSet<game::Player> {
+    // The `Player` struct is a bit awkward. Mistakes were made...
+    player_codes
+        .iter()
+        .enumerate()
+        .map(|(num, code)| (num + 1, code))
+        .fold(HashSet::new(), |mut ai, (num, code)| {
+            if *code == 2 {
+                ai.insert(map::player(num));
+            }
+            ai
+        })
 }
 
-#[macro_export]
-macro_rules! jslog {
-    ($($t:tt)*) => (log(&format!($($t)*)))
+/// Which of the seats are taken. A code of `1` is human and `2` is AI, anything else is
+/// an empty seat.
+pub (crate) fn seats_from_codes(player_codes: &[u8]) -> [bool; map::MAX_PLAYERS] {
+    let mut seats = [false; map::MAX_PLAYERS];
+    seats
+        .iter_mut()
+        .zip(player_codes)
+        .for_each(|(seat, code)| *seat = *code == 1 || *code == 2);
+    seats
 }
 
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn init() {
+    utils::set_console_logger();
     jslog!("Setting default panic hook.");
     utils::set_panic_hook();
 }
 
-#[wasm_bindgen]
-pub fn game_3x3_init(board_top_left: Point, hex_radius: u32) -> play::Game {
-    let template = grid::generate_template(3, 3, board_top_left, hex_radius);
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn game_3x3_init(
+    board_top_left: Point, hex_radius: u32, orientation: Orientation,
+) -> play::Game {
+    let template = grid::generate_template(3, 3, board_top_left, hex_radius, orientation);
     let board = game::canned_3x3_start01();
     jslog!("Getting board");
     let session = match session::Setup::new()
@@ -60,25 +105,120 @@ pub fn game_3x3_init(board_top_left: Point, hex_radius: u32) -> play::Game {
     play::Game::new(session, HashSet::new(), 1, template)
 }
 
-#[wasm_bindgen]
-pub fn start_new_game(
+/// Everything `start_new_game` needs to roll a board. Start from `GameSettings::new` and
+/// change what's wanted with the `with_` methods. Two seats, a human and then an AI, on a
+/// plain rectangle are played unless told otherwise.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GameSettings {
     board_size: u32,
     board_top_left: Point,
     hex_radius: u32,
     move_limit: u8,
-    player1_code: u8,
-    player2_code: u8,
-    player3_code: u8,
-    player4_code: u8,
+    player_codes: [u8; map::MAX_PLAYERS],
     ai_compute_horizon: usize,
-) -> play::Game {
-    let template = grid::generate_template(
-        board_size, board_size, board_top_left, hex_radius
+    balanced: bool,
+    balance_tolerance: u32,
+    orientation: Orientation,
+    shape: Shape,
+    lakes: u32,
+    seed: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl GameSettings {
+    pub fn new(board_size: u32, board_top_left: Point, hex_radius: u32) -> GameSettings {
+        GameSettings {
+            board_size,
+            board_top_left,
+            hex_radius,
+            move_limit: 3,
+            player_codes: [1, 2, 0, 0],
+            ai_compute_horizon: 3,
+            balanced: false,
+            balance_tolerance: 2,
+            orientation: Orientation::Pointy,
+            shape: Shape::Rectangle,
+            lakes: 0,
+            seed: 1,
+        }
+    }
+
+    pub fn with_move_limit(mut self, move_limit: u8) -> GameSettings {
+        self.move_limit = move_limit;
+        self
+    }
+
+    /// A code of `1` is human and `2` is AI, anything else is an empty seat.
+    pub fn with_players(
+        mut self, player1_code: u8, player2_code: u8, player3_code: u8, player4_code: u8,
+    ) -> GameSettings {
+        self.player_codes = [player1_code, player2_code, player3_code, player4_code];
+        self
+    }
+
+    pub fn with_ai_compute_horizon(mut self, ai_compute_horizon: usize) -> GameSettings {
+        self.ai_compute_horizon = ai_compute_horizon;
+        self
+    }
+
+    /// Keep rolling until no two players are further apart than the `tolerance` on any
+    /// measure of `analysis::analyse`.
+    pub fn with_balance(mut self, tolerance: u32) -> GameSettings {
+        self.balanced = true;
+        self.balance_tolerance = tolerance;
+        self
+    }
+
+    pub fn with_orientation(mut self, orientation: Orientation) -> GameSettings {
+        self.orientation = orientation;
+        self
+    }
+
+    pub fn with_shape(mut self, shape: Shape) -> GameSettings {
+        self.shape = shape;
+        self
+    }
+
+    /// Number of lakes of water to grow across the land.
+    pub fn with_lakes(mut self, lakes: u32) -> GameSettings {
+        self.lakes = lakes;
+        self
+    }
+
+    /// Seed for the terrain. The same settings and seed always grow the same lakes.
+    pub fn with_seed(mut self, seed: u32) -> GameSettings {
+        self.seed = seed;
+        self
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn start_new_game(settings: &GameSettings) -> play::Game {
+    let GameSettings {
+        board_size,
+        board_top_left,
+        hex_radius,
+        move_limit,
+        player_codes,
+        ai_compute_horizon,
+        balanced,
+        balance_tolerance,
+        orientation,
+        shape,
+        lakes,
+        seed,
+    } = *settings;
+    let mut template = grid::generate_template(
+        board_size, board_size, board_top_left, hex_radius, orientation,
     );
+    template.mask(&grid::shape_mask(shape, board_size, board_size));
+    let mut rng = rng::Rng::new(seed as u64);
+    terrain::lakes(&template, lakes, &mut rng)
+        .into_iter()
+        .for_each(|index| template.flood(index));
     jslog!("Generating new random board.");
 
-    let player_codes = [player1_code, player2_code, player3_code, player4_code];
-
     // The `Players` struct is a bit awkward...
     let players = player_codes
         .iter()
@@ -90,20 +230,35 @@ pub fn start_new_game(
             }
         });
 
-    // So is the `Player` struct. Mistakes were made...
-    let ai_players = player_codes
-        .iter()
-        .enumerate()
-        .map(|(num, code)| (num + 1, code))
-        .fold(HashSet::new(), |mut ai, (num, code)| {
-            if *code == 2 {
-                ai.insert(game::Player::new(num, (64 + num as u8).into()));
-            }
-            ai
-        });
-    
+    let ai_players = ai_players_from_codes(&player_codes);
+
     let move_limit = NonZeroU8::new(move_limit).unwrap_or(NonZeroU8::new(2).unwrap());
-    let board = game::generate_random_board(board_size, board_size, players);
+
+    // Hexes off the board and under water are handed to nobody. Anyone left with no hexes
+    // at all isn't seated.
+    let lay_terrain = |board: game::Board| if shape == Shape::Rectangle && lakes == 0 {
+        board
+    } else {
+        notation::Snapshot::from_board(&board, &template, move_limit).board()
+    };
+
+    let board = if balanced {
+        jslog!("Rolling for a balanced board.");
+        let tolerance = analysis::Tolerance::uniform(balance_tolerance);
+        let board = analysis::generate_balanced_board(
+            &template,
+            players,
+            &tolerance,
+            analysis::BALANCE_ATTEMPTS,
+            lay_terrain,
+        );
+        analysis::analyse(&board, &template)
+            .iter()
+            .for_each(|stats| jslog!("{}", stats));
+        board
+    } else {
+        lay_terrain(game::generate_random_board(board_size, board_size, players))
+    };
     let session = match session::Setup::new()
         .set_board(board)
         .set_move_limit(move_limit)
@@ -117,3 +272,212 @@ pub fn start_new_game(
     jslog!("Created session");
     play::Game::new(session, ai_players, ai_compute_horizon, template)
 }
+
+/// Start a game from a hand authored map. See the `map` module for the format. Players
+/// are seated according to the map, the player codes only decide which of them are AI.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn start_game_from_map(
+    map_text: &str,
+    board_top_left: Point,
+    hex_radius: u32,
+    orientation: Orientation,
+    move_limit: u8,
+    player1_code: u8,
+    player2_code: u8,
+    player3_code: u8,
+    player4_code: u8,
+    ai_compute_horizon: usize,
+) -> Result<play::Game, Failure> {
+    let map = map::Map::parse(map_text)
+        .map_err(|e| failure(&e.to_string()))?;
+    jslog!("Loaded map '{}' by '{}'.", map.name(), map.author());
+
+    start_map_game(
+        &map,
+        board_top_left,
+        hex_radius,
+        orientation,
+        move_limit,
+        &[player1_code, player2_code, player3_code, player4_code],
+        ai_compute_horizon,
+    )
+}
+
+pub (crate) fn start_map_game(
+    map: &map::Map,
+    board_top_left: Point,
+    hex_radius: u32,
+    orientation: Orientation,
+    move_limit: u8,
+    player_codes: &[u8],
+    ai_compute_horizon: usize,
+) -> Result<play::Game, Failure> {
+    let template = map.template(board_top_left, hex_radius, orientation);
+    let ai_players = ai_players_from_codes(player_codes);
+    let move_limit = NonZeroU8::new(move_limit).unwrap_or(NonZeroU8::new(2).unwrap());
+
+    let session = session::Setup::new()
+        .set_board(map.board())
+        .set_move_limit(move_limit)
+        .session()
+        .map_err(|e| failure(&format!("Failed to create game session: {}", &e)))?;
+    jslog!("Created session");
+    Ok(play::Game::new(session, ai_players, ai_compute_horizon, template))
+}
+
+/// Start a game from exactly the position given in the one line notation of the
+/// `notation` module. The player codes only decide which of the players are AI.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn start_game_from_position(
+    position: &str,
+    board_top_left: Point,
+    hex_radius: u32,
+    orientation: Orientation,
+    player1_code: u8,
+    player2_code: u8,
+    player3_code: u8,
+    player4_code: u8,
+    ai_compute_horizon: usize,
+) -> Result<play::Game, Failure> {
+    let snapshot = notation::Snapshot::parse(position)
+        .map_err(|e| failure(&e.to_string()))?;
+
+    let template = snapshot.template(board_top_left, hex_radius, orientation);
+    let player_codes = [player1_code, player2_code, player3_code, player4_code];
+    let ai_players = ai_players_from_codes(&player_codes);
+
+    let session = snapshot.session().map_err(|e| failure(&e))?;
+    jslog!("Created session from position {}", &snapshot);
+    Ok(play::Game::new(session, ai_players, ai_compute_horizon, template))
+}
+
+/// Start editing a blank board for the players chosen by the codes, coded the same as
+/// for `GameSettings::with_players`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn start_editor(
+    columns: u32,
+    rows: u32,
+    board_top_left: Point,
+    hex_radius: u32,
+    orientation: Orientation,
+    player1_code: u8,
+    player2_code: u8,
+    player3_code: u8,
+    player4_code: u8,
+) -> editor::Editor {
+    let seats = seats_from_codes(&[player1_code, player2_code, player3_code, player4_code]);
+    let map = editor::Editor::blank(columns, rows, &seats);
+    editor::Editor::new(&map, board_top_left, hex_radius, orientation, seats)
+}
+
+/// Start editing a hand authored map for the players chosen by the codes.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn edit_map(
+    map_text: &str,
+    board_top_left: Point,
+    hex_radius: u32,
+    orientation: Orientation,
+    player1_code: u8,
+    player2_code: u8,
+    player3_code: u8,
+    player4_code: u8,
+) -> Result<editor::Editor, Failure> {
+    let map = map::Map::parse(map_text)
+        .map_err(|e| failure(&e.to_string()))?;
+    let seats = seats_from_codes(&[player1_code, player2_code, player3_code, player4_code]);
+    Ok(editor::Editor::new(&map, board_top_left, hex_radius, orientation, seats))
+}
+
+/// Number of bundled puzzles.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn puzzle_count() -> usize {
+    puzzle::BUNDLED.len()
+}
+
+/// Name of a bundled puzzle. Wraps around if the index is out of bounds.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn puzzle_name(index: usize) -> String {
+    let puzzles = puzzle::bundled();
+    puzzles[index % puzzles.len()].name().to_owned()
+}
+
+/// Start playing a bundled puzzle. Wraps around if the index is out of bounds.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn start_puzzle(
+    index: usize, board_top_left: Point, hex_radius: u32, orientation: Orientation,
+) -> Result<play::Game, Failure> {
+    let puzzles = puzzle::bundled();
+    let puzzle = puzzles[index % puzzles.len()].to_owned();
+    jslog!("Starting puzzle '{}'. {}", puzzle.name(), puzzle.description());
+
+    let template = puzzle.snapshot().template(board_top_left, hex_radius, orientation);
+    let session = puzzle.snapshot().session().map_err(|e| failure(&e))?;
+    let mut game = play::Game::new(session, HashSet::new(), 1, template);
+    game.set_puzzle(puzzle);
+    Ok(game)
+}
+
+/// Solve every bundled puzzle and report the outcome of each, one per line.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn verify_bundled_puzzles() -> String {
+    puzzle::verify_bundled()
+        .into_iter()
+        .map(|(name, outcome)| match outcome {
+            Ok(solution) => format!("{}: solved with choices {:?}", name, solution),
+            Err(e) => format!("{}: UNSOLVABLE. {}", name, e),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Play AI self-play games looking for puzzles. Returns the puzzles found one per line in
+/// the format of the `puzzle` module.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn generate_puzzles(
+    board_size: u32,
+    players: usize,
+    move_limit: u8,
+    ai_compute_horizon: usize,
+    games: usize,
+) -> Result<String, Failure> {
+    let settings = generator::Settings {
+        columns: board_size,
+        rows: board_size,
+        players,
+        move_limit: NonZeroU8::new(move_limit).unwrap_or(NonZeroU8::new(2).unwrap()),
+        ai_compute_horizon,
+        games,
+        ..generator::Settings::default()
+    };
+
+    let found = generator::generate(&settings).map_err(|e| failure(&e))?;
+    jslog!("Generated {} puzzles.", found.len());
+    Ok(found
+        .iter()
+        .map(|generated| generated.to_string())
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+/// Start the guided tutorial from its first step.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn start_tutorial(
+    board_top_left: Point, hex_radius: u32, orientation: Orientation,
+) -> Result<tutorial::Tutorial, Failure> {
+    tutorial::Tutorial::new(board_top_left, hex_radius, orientation)
+        .map_err(|e| failure(&e))
+}
+
+/// Number of `i32`s each hex takes up in `Tessellation::pack`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn packed_stride() -> usize {
+    hex::PACKED_STRIDE
+}
+
+/// The board drawn as a standalone SVG document with dice in the given CSS colours.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn board_svg(
+    tessellation: &Tessellation, die_colour: &str, dot_colour: &str,
+) -> String {
+    svg::render(tessellation, &theme::Theme::new(die_colour, dot_colour))
+}
@@ -0,0 +1,480 @@
+//! Hand authored maps. A map is a small text file such as;
+//!
+//! ```text
+//! // Lines starting with a double slash are comments.
+//! name: Crossing
+//! author: kvsari
+//! size: 3x3
+//! .  B1 A3
+//!  B2 #  A1
+//! A1 ~  B1
+//! ```
+//!
+//! The header sets the metadata and the `size` as columns by rows. Each row of the grid
+//! is made up of whitespace separated cells. A cell is the owning player letter (`A` is
+//! player 1, `B` player 2 and so on) followed by the number of dice. A `#` marks a blocked
+//! hex that no one owns and a `~` is water, which no one can hold or cross either. A `.`
+//! isn't on the board at all, for boards that aren't rectangles. Leading spaces are
+//! allowed so odd rows can be indented to look like the board.
+use std::{error, fmt};
+
+use dicey_dice::{hexagon, game};
+use dicey_dice::game::{Holding, Board};
+
+use crate::dice::MAX_DICE;
+use crate::grid::{self, Template};
+use crate::primitive::{Orientation, Point};
+
+/// Owner of blocked and water hexes. Players are numbered from one so this never clashes.
+pub const NEUTRAL: usize = 0;
+
+/// Maximum number of players a map can seat.
+pub const MAX_PLAYERS: usize = 4;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Cell {
+    Blocked,
+    Water,
+
+    /// Taken off the board altogether.
+    Absent,
+    Held { owner: usize, dice: u8 },
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cell::Blocked => write!(f, "#"),
+            Cell::Water => write!(f, "~"),
+            Cell::Absent => write!(f, "."),
+            Cell::Held { owner, dice } => write!(f, "{}{}", player_letter(*owner), dice),
+        }
+    }
+}
+
+pub fn player_letter(number: usize) -> char {
+    (64 + number as u8).into()
+}
+
+pub fn player_number(letter: char) -> Option<usize> {
+    match letter {
+        'A'..='Z' => Some(letter as usize - 64),
+        _ => None,
+    }
+}
+
+/// Make a `Player` the same way the rest of the crate does.
+pub (crate) fn player(number: usize) -> game::Player {
+    game::Player::new(number, player_letter(number))
+}
+
+/// Seat the players. Each `true` in `present` takes a turn, each `false` is skipped. The
+/// first present player is the current player.
+pub (crate) fn seat_players(present: &[bool]) -> game::Players {
+    present
+        .iter()
+        .fold(game::Players::new(present.len()), |players, here| {
+            if *here {
+                players.next()
+            } else {
+                players.remove_current()
+            }
+        })
+}
+
+/// Which of the `MAX_PLAYERS` seats hold at least one of the `cells`.
+pub (crate) fn present(cells: &[Cell]) -> [bool; MAX_PLAYERS] {
+    let mut present = [false; MAX_PLAYERS];
+    cells
+        .iter()
+        .for_each(|cell| if let Cell::Held { owner, .. } = cell {
+            present[*owner - 1] = true;
+        });
+    present
+}
+
+/// Build a `Board` out of `cells` laid out row by row. Blocked, water and absent cells are
+/// given to the `NEUTRAL` owner with no dice.
+pub (crate) fn build_board(
+    columns: u32,
+    rows: u32,
+    cells: &[Cell],
+    players: game::Players,
+    captured_dice: u8,
+    moved: u8,
+) -> Board {
+    let mut holdings = cells
+        .iter()
+        .map(|cell| match cell {
+            Cell::Blocked | Cell::Water | Cell::Absent => Holding::new(player(NEUTRAL), 0),
+            Cell::Held { owner, dice } => Holding::new(player(*owner), *dice),
+        });
+
+    let grid = hexagon::Rectangular::generate(columns, rows, Holding::default())
+        .fork_with(|_, _| holdings.next().unwrap());
+
+    Board::new(players, grid, captured_dice, moved)
+}
+
+/// Apply any blocked, water and absent cells to the `template` so they aren't drawn as
+/// playable.
+pub (crate) fn block_template(template: &mut Template, cells: &[Cell]) {
+    cells
+        .iter()
+        .enumerate()
+        .for_each(|(index, cell)| match cell {
+            Cell::Blocked => template.block(index),
+            Cell::Water => template.flood(index),
+            Cell::Absent => template.remove(index),
+            Cell::Held { .. } => (),
+        });
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnknownHeader(String),
+    BadSize(String),
+    MissingSize,
+    BadCell(String),
+    BadOwner(char),
+    BadDice(String),
+    RowLength { expected: u32, found: u32 },
+    RowCount { expected: u32, found: u32 },
+    NoPlayers,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::UnknownHeader(key) => write!(f, "Unknown header '{}'", key),
+            ErrorKind::BadSize(size) => {
+                write!(f, "Size '{}' should be columns by rows such as 4x4", size)
+            },
+            ErrorKind::MissingSize => {
+                write!(f, "The size header must come before the grid")
+            },
+            ErrorKind::BadCell(cell) => write!(f, "Unreadable cell '{}'", cell),
+            ErrorKind::BadOwner(owner) => {
+                write!(f, "Owner '{}' should be a player letter from A to D", owner)
+            },
+            ErrorKind::BadDice(dice) => {
+                write!(f, "Dice '{}' should be a number from 1 to {}", dice, MAX_DICE)
+            },
+            ErrorKind::RowLength { expected, found } => {
+                write!(f, "Row has {} cells but should have {}", found, expected)
+            },
+            ErrorKind::RowCount { expected, found } => {
+                write!(f, "Map has {} rows but should have {}", found, expected)
+            },
+            ErrorKind::NoPlayers => write!(f, "Map needs at least two players"),
+        }
+    }
+}
+
+/// Where a map went wrong. Both `line` and `column` count from one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    column: usize,
+    kind: ErrorKind,
+}
+
+impl ParseError {
+    fn new(line: usize, column: usize, kind: ErrorKind) -> Self {
+        ParseError { line, column, kind }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}, column {}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// Read a single cell such as `B3`, `#`, `~` or `.`.
+pub (crate) fn parse_cell(token: &str) -> Result<Cell, ErrorKind> {
+    match token {
+        "#" => return Ok(Cell::Blocked),
+        "~" => return Ok(Cell::Water),
+        "." => return Ok(Cell::Absent),
+        _ => (),
+    }
+
+    let mut chars = token.chars();
+    let letter = chars.next().ok_or_else(|| ErrorKind::BadCell(token.to_owned()))?;
+    let owner = player_number(letter)
+        .filter(|owner| *owner <= MAX_PLAYERS)
+        .ok_or(ErrorKind::BadOwner(letter))?;
+    let dice_text = chars.as_str();
+    let dice: u8 = dice_text
+        .parse()
+        .ok()
+        .filter(|dice| *dice > 0 && *dice <= MAX_DICE)
+        .ok_or_else(|| ErrorKind::BadDice(dice_text.to_owned()))?;
+
+    Ok(Cell::Held { owner, dice })
+}
+
+/// Read a size such as `4x3`.
+pub (crate) fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let mut parts = size.trim().splitn(2, 'x');
+    let columns: u32 = parts.next()?.trim().parse().ok()?;
+    let rows: u32 = parts.next()?.trim().parse().ok()?;
+    if columns > 0 && rows > 0 {
+        Some((columns, rows))
+    } else {
+        None
+    }
+}
+
+/// A hand authored board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Map {
+    name: String,
+    author: String,
+    columns: u32,
+    rows: u32,
+    cells: Vec<Cell>,
+}
+
+impl Map {
+    pub fn new(name: &str, author: &str, columns: u32, rows: u32, cells: &[Cell]) -> Self {
+        Map {
+            name: name.to_owned(),
+            author: author.to_owned(),
+            columns,
+            rows,
+            cells: cells.to_vec(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut name = String::new();
+        let mut author = String::new();
+        let mut size: Option<(u32, u32)> = None;
+        let mut cells: Vec<Cell> = Vec::new();
+        let mut found_rows = 0;
+        let mut last_line = 0;
+
+        for (count, line) in text.lines().enumerate() {
+            let line_no = count + 1;
+            last_line = line_no;
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() || trimmed.starts_with("//") {
+                continue;
+            }
+
+            if let Some(colon) = trimmed.find(':') {
+                let key = trimmed[..colon].trim();
+                let value = trimmed[colon + 1..].trim();
+                let column = line.find(':').map(|c| column_at(line, c)).unwrap_or(1);
+                match key {
+                    "name" => name = value.to_owned(),
+                    "author" => author = value.to_owned(),
+                    "size" => {
+                        size = Some(parse_size(value).ok_or_else(|| ParseError::new(
+                            line_no, column + 1, ErrorKind::BadSize(value.to_owned()),
+                        ))?);
+                    },
+                    _ => {
+                        let column = column_at(line, line.len() - line.trim_start().len());
+                        return Err(ParseError::new(
+                            line_no, column, ErrorKind::UnknownHeader(key.to_owned()),
+                        ));
+                    },
+                }
+                continue;
+            }
+
+            let (columns, rows) = size.ok_or_else(|| {
+                ParseError::new(line_no, 1, ErrorKind::MissingSize)
+            })?;
+
+            let mut row_cells = 0;
+            for (column, token) in tokens(line) {
+                let cell = parse_cell(token)
+                    .map_err(|kind| ParseError::new(line_no, column, kind))?;
+                cells.push(cell);
+                row_cells += 1;
+            }
+
+            if row_cells != columns {
+                return Err(ParseError::new(
+                    line_no,
+                    column_at(line, line.len()),
+                    ErrorKind::RowLength { expected: columns, found: row_cells },
+                ));
+            }
+
+            found_rows += 1;
+            if found_rows > rows {
+                return Err(ParseError::new(
+                    line_no, 1, ErrorKind::RowCount { expected: rows, found: found_rows },
+                ));
+            }
+        }
+
+        let (columns, rows) = size.ok_or_else(|| {
+            ParseError::new(last_line.max(1), 1, ErrorKind::MissingSize)
+        })?;
+
+        if found_rows != rows {
+            return Err(ParseError::new(
+                last_line.max(1),
+                1,
+                ErrorKind::RowCount { expected: rows, found: found_rows },
+            ));
+        }
+
+        let map = Map { name, author, columns, rows, cells };
+        if map.player_count() < 2 {
+            return Err(ParseError::new(last_line.max(1), 1, ErrorKind::NoPlayers));
+        }
+
+        Ok(map)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Which of the `MAX_PLAYERS` seats have at least one hex.
+    pub fn present(&self) -> [bool; MAX_PLAYERS] {
+        present(&self.cells)
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.present().iter().filter(|p| **p).count()
+    }
+
+    pub fn board(&self) -> Board {
+        build_board(
+            self.columns, self.rows, &self.cells, seat_players(&self.present()), 0, 0,
+        )
+    }
+
+    /// Drawing template matching this map, blocked, water and absent hexes included.
+    pub fn template(
+        &self, start: Point, radius: u32, orientation: Orientation,
+    ) -> Template {
+        let mut template = grid::generate_template(
+            self.columns, self.rows, start, radius, orientation,
+        );
+        block_template(&mut template, &self.cells);
+        template
+    }
+}
+
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.name.is_empty() {
+            writeln!(f, "name: {}", self.name)?;
+        }
+        if !self.author.is_empty() {
+            writeln!(f, "author: {}", self.author)?;
+        }
+        writeln!(f, "size: {}x{}", self.columns, self.rows)?;
+
+        for (row, cells) in self.cells.chunks(self.columns as usize).enumerate() {
+            if row % 2 == 1 {
+                write!(f, " ")?;
+            }
+            let line: Vec<String> = cells
+                .iter()
+                .map(|c| format!("{:2}", c.to_string()))
+                .collect();
+            writeln!(f, "{}", line.join(" ").trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// One based column, counted in characters, of the byte `index` into `line`.
+fn column_at(line: &str, index: usize) -> usize {
+    line[..index].chars().count() + 1
+}
+
+/// Split a line into whitespace separated tokens along with their one based column.
+fn tokens(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    line.char_indices()
+        .fold(Vec::new(), |mut tokens: Vec<(usize, usize)>, (index, c)| {
+            if c.is_whitespace() {
+                return tokens;
+            }
+            match tokens.last_mut() {
+                Some((_, end)) if *end == index => *end = index + c.len_utf8(),
+                _ => tokens.push((index, index + c.len_utf8())),
+            }
+            tokens
+        })
+        .into_iter()
+        .map(move |(start, end)| (column_at(line, start), &line[start..end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOLED: &str = "size: 3x2\nA1 .  B1\n .  ~  #\n";
+
+    #[test]
+    fn reads_and_writes_absent_cells() {
+        let map = Map::parse(HOLED).unwrap();
+        assert_eq!(map.cells()[1], Cell::Absent);
+        assert_eq!(map.cells()[3], Cell::Absent);
+        assert_eq!(map.to_string(), HOLED);
+    }
+
+    #[test]
+    fn takes_absent_cells_off_the_template() {
+        let template = Map::parse(HOLED)
+            .unwrap()
+            .template(Point::default(), 10, Orientation::Pointy);
+        assert!(template.is_absent(1) && template.is_absent(3));
+        assert!(template.is_water(4) && !template.is_absent(4));
+        assert!(template.is_blocked(5) && !template.is_absent(5));
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        // An ideographic space is whitespace three bytes long.
+        let error = Map::parse("size: 3x1\nA1\u{3000}B1\n").unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::RowLength { expected: 3, found: 2 });
+        assert_eq!((error.line(), error.column()), (2, 6));
+
+        let error = Map::parse("size: 2x1\nA1\u{3000}Z1\n").unwrap_err();
+        assert_eq!((error.line(), error.column()), (2, 4));
+    }
+}
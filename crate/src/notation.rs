@@ -0,0 +1,397 @@
+//! One line notation for a board and the state of the turn. Handy for bug reports, puzzles
+//! and sharing a game through a URL fragment. It looks like;
+//!
+//! ```text
+//! A2B1A3/B2#A1/A1A1B1_A_0_0_3
+//! ```
+//!
+//! The board comes first with the rows separated by `/`. Each cell is the owning player
+//! letter followed by a single dice digit, a `#` for a blocked hex, a `~` for water or a
+//! `.` for a hex that isn't on the board.
+//! Then come the `_` separated turn fields; the current player letter, moves made this
+//! turn, dice captured this turn and the move limit. Nothing in there needs escaping in a
+//! URL.
+use std::num::NonZeroU8;
+use std::{error, fmt};
+
+use dicey_dice::session;
+use dicey_dice::game::Board;
+
+use crate::grid::{self, Template};
+use crate::map::{self, Cell, MAX_PLAYERS, NEUTRAL};
+use crate::primitive::{Orientation, Point};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    MissingField(&'static str),
+    BadCell(map::ErrorKind),
+    RowLength { expected: u32, found: u32 },
+    BadPlayer(String),
+    AbsentPlayer(char),
+    BadNumber(String),
+    MovesOverLimit { moved: u8, limit: u8 },
+    TooManyFields,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::MissingField(field) => write!(f, "Missing the {} field", field),
+            ErrorKind::BadCell(kind) => write!(f, "{}", kind),
+            ErrorKind::RowLength { expected, found } => {
+                write!(f, "Row has {} cells but should have {}", found, expected)
+            },
+            ErrorKind::BadPlayer(player) => write!(f, "'{}' isn't a player letter", player),
+            ErrorKind::AbsentPlayer(player) => {
+                write!(f, "Player '{}' has no hexes on the board", player)
+            },
+            ErrorKind::BadNumber(number) => write!(f, "'{}' isn't a usable number", number),
+            ErrorKind::MovesOverLimit { moved, limit } => {
+                write!(f, "{} moves made is over the move limit of {}", moved, limit)
+            },
+            ErrorKind::TooManyFields => write!(f, "Too many fields"),
+        }
+    }
+}
+
+/// Where the notation went wrong. The `offset` is the one based character position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotationError {
+    offset: usize,
+    kind: ErrorKind,
+}
+
+impl NotationError {
+    fn new(offset: usize, kind: ErrorKind) -> Self {
+        NotationError { offset, kind }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Character {}: {}", self.offset, self.kind)
+    }
+}
+
+impl error::Error for NotationError {}
+
+/// A board with the state of the current turn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    columns: u32,
+    rows: u32,
+    cells: Vec<Cell>,
+    current: usize,
+    moved: u8,
+    captured: u8,
+    move_limit: NonZeroU8,
+}
+
+impl Snapshot {
+    /// Capture the `board`. The `template` tells which hexes are blocked or water.
+    pub fn from_board(board: &Board, template: &Template, move_limit: NonZeroU8) -> Self {
+        Snapshot::capture(board, template.columns(), template.rows(), move_limit, |index| {
+            if template.is_absent(index) {
+                Some(Cell::Absent)
+            } else if template.is_water(index) {
+                Some(Cell::Water)
+            } else if template.is_blocked(index) {
+                Some(Cell::Blocked)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Capture a `columns` by `rows` board that has no template, such as one only played
+    /// by the AI. Hexes held by `NEUTRAL` are taken as blocked.
+    pub fn from_plain_board(
+        board: &Board, columns: u32, rows: u32, move_limit: NonZeroU8,
+    ) -> Self {
+        Snapshot::capture(board, columns, rows, move_limit, |_| None)
+    }
+
+    /// The `terrain` of a hex is its cell if it isn't land.
+    fn capture<F>(
+        board: &Board, columns: u32, rows: u32, move_limit: NonZeroU8, terrain: F,
+    ) -> Self
+    where F: Fn(usize) -> Option<Cell> {
+        let cells: Vec<Cell> = board
+            .grid()
+            .iter()
+            .enumerate()
+            .map(|(index, hex)| {
+                let owner = *hex.data().owner().number();
+                terrain(index).unwrap_or_else(|| if owner == NEUTRAL {
+                    Cell::Blocked
+                } else {
+                    Cell::Held { owner, dice: hex.data().dice() }
+                })
+            })
+            .collect();
+
+        Snapshot {
+            columns,
+            rows,
+            cells,
+            current: *board.players().current().number(),
+            moved: board.moved(),
+            captured: *board.captured_dice(),
+            move_limit,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, NotationError> {
+        let text = text.trim();
+
+        // Pair each field with its offset so errors can point at it.
+        let mut offset = 1;
+        let fields: Vec<(usize, &str)> = text
+            .split('_')
+            .map(|field| {
+                let field_offset = offset;
+                offset += field.len() + 1;
+                (field_offset, field)
+            })
+            .collect();
+
+        let field = |index: usize, name: &'static str| {
+            fields
+                .get(index)
+                .map(|f| *f)
+                .filter(|(_, f)| !f.is_empty())
+                .ok_or_else(|| {
+                    NotationError::new(text.len() + 1, ErrorKind::MissingField(name))
+                })
+        };
+
+        let (_, grid) = field(0, "board")?;
+        let (current_offset, current) = field(1, "current player")?;
+        let (moved_offset, moved) = field(2, "moves made")?;
+        let (captured_offset, captured) = field(3, "captured dice")?;
+        let (limit_offset, limit) = field(4, "move limit")?;
+
+        if let Some((extra_offset, _)) = fields.get(5) {
+            return Err(NotationError::new(*extra_offset, ErrorKind::TooManyFields));
+        }
+
+        let (columns, rows, cells) = parse_grid(grid)?;
+
+        let current = single_char(current)
+            .and_then(map::player_number)
+            .filter(|number| *number <= MAX_PLAYERS)
+            .ok_or_else(|| NotationError::new(
+                current_offset, ErrorKind::BadPlayer(current.to_owned()),
+            ))?;
+        let current_present = cells
+            .iter()
+            .any(|cell| match cell {
+                Cell::Held { owner, .. } => *owner == current,
+                Cell::Blocked | Cell::Water | Cell::Absent => false,
+            });
+        if !current_present {
+            return Err(NotationError::new(
+                current_offset, ErrorKind::AbsentPlayer(map::player_letter(current)),
+            ));
+        }
+
+        let number = |field_offset: usize, field: &str| -> Result<u8, NotationError> {
+            field.parse().map_err(|_| NotationError::new(
+                field_offset, ErrorKind::BadNumber(field.to_owned()),
+            ))
+        };
+        let moved = number(moved_offset, moved)?;
+        let captured = number(captured_offset, captured)?;
+        let move_limit = NonZeroU8::new(number(limit_offset, limit)?).ok_or_else(|| {
+            NotationError::new(limit_offset, ErrorKind::BadNumber(limit.to_owned()))
+        })?;
+        if moved > move_limit.get() {
+            return Err(NotationError::new(
+                moved_offset, ErrorKind::MovesOverLimit { moved, limit: move_limit.get() },
+            ));
+        }
+
+        Ok(Snapshot { columns, rows, cells, current, moved, captured, move_limit })
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn move_limit(&self) -> NonZeroU8 {
+        self.move_limit
+    }
+
+    /// Whether the cell at `index` can't be held, such as a blocked or water hex.
+    pub fn is_blocked(&self, index: usize) -> bool {
+        match self.cells.get(index) {
+            Some(Cell::Held { .. }) => false,
+            _ => true,
+        }
+    }
+
+    /// Which of the `MAX_PLAYERS` seats have at least one hex.
+    pub fn present(&self) -> [bool; MAX_PLAYERS] {
+        map::present(&self.cells)
+    }
+
+    /// Rebuild the board with the current player to move.
+    pub fn board(&self) -> Board {
+        let mut players = map::seat_players(&self.present());
+        for _ in 0..MAX_PLAYERS {
+            if *players.current().number() == self.current {
+                break;
+            }
+            players = players.next();
+        }
+
+        map::build_board(
+            self.columns, self.rows, &self.cells, players, self.captured, self.moved,
+        )
+    }
+
+    /// Start a session playing on from this snapshot.
+    pub fn session(&self) -> Result<session::Session, String> {
+        session::Setup::new()
+            .set_board(self.board())
+            .set_move_limit(self.move_limit)
+            .session()
+            .map_err(|e| format!("Failed to create game session: {}", &e))
+    }
+
+    /// Drawing template matching this snapshot, blocked, water and absent hexes included.
+    pub fn template(
+        &self, start: Point, radius: u32, orientation: Orientation,
+    ) -> Template {
+        let mut template = grid::generate_template(
+            self.columns, self.rows, start, radius, orientation,
+        );
+        map::block_template(&mut template, &self.cells);
+        template
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (row, cells) in self.cells.chunks(self.columns as usize).enumerate() {
+            if row > 0 {
+                write!(f, "/")?;
+            }
+            for cell in cells {
+                write!(f, "{}", cell)?;
+            }
+        }
+        write!(
+            f,
+            "_{}_{}_{}_{}",
+            map::player_letter(self.current), self.moved, self.captured, self.move_limit,
+        )
+    }
+}
+
+fn single_char(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Read the `/` separated rows. Every row must be as long as the first.
+fn parse_grid(grid: &str) -> Result<(u32, u32, Vec<Cell>), NotationError> {
+    let mut cells = Vec::new();
+    let mut columns: Option<u32> = None;
+    let mut rows = 0;
+    let mut offset = 1;
+
+    for row in grid.split('/') {
+        let mut row_cells = 0;
+        let mut chars = row.char_indices().peekable();
+
+        while let Some((start, c)) = chars.next() {
+            let end = if c == '#' || c == '~' || c == '.' {
+                start + 1
+            } else {
+                match chars.next() {
+                    Some((dice_index, dice)) => dice_index + dice.len_utf8(),
+                    None => start + c.len_utf8(),
+                }
+            };
+            let cell = map::parse_cell(&row[start..end])
+                .map_err(|kind| {
+                    NotationError::new(offset + start, ErrorKind::BadCell(kind))
+                })?;
+            cells.push(cell);
+            row_cells += 1;
+        }
+
+        let expected = *columns.get_or_insert(row_cells);
+        if row_cells != expected || row_cells == 0 {
+            return Err(NotationError::new(
+                offset, ErrorKind::RowLength { expected, found: row_cells },
+            ));
+        }
+
+        rows += 1;
+        offset += row.len() + 1;
+    }
+
+    Ok((columns.unwrap_or(0), rows, cells))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let text = "A2B1A3/B2#A1/A1A1B1_A_1_0_3";
+        assert_eq!(Snapshot::parse(text).unwrap().to_string(), text);
+    }
+
+    #[test]
+    fn keeps_absent_hexes_off_the_board() {
+        let text = "A2.B1/B2#A1/.~B1_A_0_0_3";
+        let snapshot = Snapshot::parse(text).unwrap();
+        assert_eq!(snapshot.to_string(), text);
+        assert_eq!(snapshot.cells[1], Cell::Absent);
+
+        let template = snapshot.template(Point::default(), 10, Orientation::Pointy);
+        assert!(template.is_absent(1) && template.is_absent(6));
+        assert!(!template.is_absent(4) && !template.is_absent(7));
+        let board = snapshot.board();
+        assert_eq!(Snapshot::from_board(&board, &template, snapshot.move_limit), snapshot);
+    }
+
+    #[test]
+    fn rejects_moves_over_the_limit() {
+        let e = Snapshot::parse("A2B1_A_5_0_3").unwrap_err();
+        assert_eq!(e.offset(), 8);
+        assert_eq!(e.kind(), &ErrorKind::MovesOverLimit { moved: 5, limit: 3 });
+    }
+
+    #[test]
+    fn allows_every_move_used() {
+        assert!(Snapshot::parse("A2B1_A_3_0_3").is_ok());
+    }
+}
@@ -1,28 +1,74 @@
 //! Game state handling stuff.
 use std::collections::HashSet;
 
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 use dicey_dice::{session, hexagon, game};
 use crate::grid::{self, Template, Tessellation};
+use crate::notation::Snapshot;
+use crate::puzzle::{Progress, Puzzle, PuzzleStatus};
 use crate::primitive::Point;
-use crate::{log, jslog};
+use crate::jslog;
+
+/// Whether the `choice` attacks a hex the `blocked` check turns down. Blocked, water and
+/// absent hexes are still engine hexes held by `NEUTRAL` so the engine offers attacks
+/// into them all the same.
+pub (crate) fn attacks_blocked<F>(
+    choice: &game::Choice, board: &game::Board, blocked: F,
+) -> bool
+where F: Fn(usize) -> bool {
+    match choice.action() {
+        game::Action::Attack(_, to_hex, _, _) => board
+            .grid()
+            .fetch_index(*to_hex)
+            .map(|index| blocked(index))
+            .unwrap_or(true),
+        _ => false,
+    }
+}
 
-/// Rely on the choice scoring to move.
-fn handle_ai_turn(choices: &[game::Choice]) -> usize {
-    let (index, _) = choices
+/// Rely on the choice scoring to move. Attacks into blocked hexes are never chosen and
+/// the turn is ended when there's nothing else left.
+fn handle_ai_turn(
+    choices: &[game::Choice], board: &game::Board, template: &Template,
+) -> usize {
+    let playable = choices
         .iter()
         .enumerate()
-        .fold((0, game::Score::default()), |(index, best), (count, choice)| {
+        .filter(|(_, choice)| {
+            !attacks_blocked(choice, board, |index| template.is_blocked(index))
+        });
+    best_choice(playable)
+        .or_else(|| end_turn_choice(choices))
+        .unwrap_or_default()
+}
+
+/// Index of the highest scoring of the scored `choices`, the earliest on a tie. `None`
+/// if there are no choices at all.
+pub fn best_choice<'a, I>(choices: I) -> Option<usize>
+where I: Iterator<Item = (usize, &'a game::Choice)> {
+    choices
+        .fold(None, |best: Option<(usize, game::Score)>, (index, choice)| {
             let score = choice.score().unwrap();
-            if score > best {
-                (count, score)
+            let better = best.as_ref().map(|(_, top)| score > *top).unwrap_or(true);
+            if better {
+                Some((index, score))
             } else {
-                (index, best)
+                best
             }
-        });
+        })
+        .map(|(index, _)| index)
+}
 
-    index
+/// Index of the choice that ends the turn rather than attacking.
+pub fn end_turn_choice(choices: &[game::Choice]) -> Option<usize> {
+    choices
+        .iter()
+        .position(|choice| match choice.action() {
+            game::Action::Attack(..) => false,
+            _ => true,
+        })
 }
 
 fn state_to_log(state: &session::State) -> String {
@@ -78,7 +124,15 @@ impl Selected {
     }
 }
 
-#[wasm_bindgen]
+/// A puzzle being played along with how it's going.
+#[derive(Debug, Clone)]
+struct PuzzleRun {
+    puzzle: Puzzle,
+    progress: Progress,
+    status: PuzzleStatus,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct Game {
     session: session::Session,
     ai_players: HashSet<game::Player>,
@@ -93,6 +147,15 @@ pub struct Game {
 
     /// Index of selected hex if any with threatened.
     selected: Option<Selected>,
+
+    /// Only set when a puzzle is being played.
+    puzzle: Option<PuzzleRun>,
+
+    /// Indexes of hexes that look different since the renderer last caught up.
+    dirty: HashSet<usize>,
+
+    /// Layout pixels per CSS pixel that clicks come in as.
+    pixel_ratio: f64,
 }
 
 impl Game {
@@ -107,6 +170,7 @@ impl Game {
         ));
         let selected = None;
         let turn = Some(session.current_turn().to_owned());
+        let dirty = (0..tessellation.as_ref().unwrap().len()).collect();
         Game {
             session,
             ai_players,
@@ -114,11 +178,77 @@ impl Game {
             template,
             tessellation,
             turn,
-            selected
+            selected,
+            puzzle: None,
+            dirty,
+            pixel_ratio: 1_f64,
+        }
+    }
+
+    /// Catch the tessellation up with the board of the current turn. Only the hexes whose
+    /// owner or dice changed are touched and they're marked dirty.
+    fn update_tessellation(&mut self) {
+        let changed = grid::update_tessellation(
+            self.tessellation.as_mut().unwrap(),
+            &self.template,
+            self.turn.as_ref().unwrap().board(),
+        );
+        self.dirty.extend(changed);
+    }
+
+    /// The tessellation as it is, for drawing without a copy.
+    pub (crate) fn current_tessellation(&self) -> &Tessellation {
+        self.tessellation.as_ref().unwrap()
+    }
+
+    /// Judge the moves made in this game against the `puzzle`. The game must have been
+    /// started from the puzzle's position.
+    pub (crate) fn set_puzzle(&mut self, puzzle: Puzzle) {
+        self.puzzle = Some(PuzzleRun {
+            puzzle,
+            progress: Progress::new(),
+            status: PuzzleStatus::Solving,
+        });
+    }
+
+    /// Index of the hex under the canvas `pixel`, in CSS pixels, if there is one.
+    pub (crate) fn index_at_pixel(&self, pixel: Point) -> Option<usize> {
+        self.tessellation.as_ref().unwrap().hex_at(pixel.scale(self.pixel_ratio))
+    }
+
+    /// Same as `select_hex_with_pixel` but picks the hex by its index for clients that
+    /// don't deal in pixels.
+    pub fn select_hex_with_index(&mut self, index: usize) -> bool {
+        if index >= self.template.columns() as usize * self.template.rows() as usize {
+            jslog!("No hexagon at index {}.", index);
+            return false;
+        }
+        let axial: hexagon::Axial = self.template.coordinate(index).into();
+        self.select_hexagon(axial.into())
+    }
+
+    /// Index of the hex selected to attack from, if any.
+    pub fn selected_index(&self) -> Option<usize> {
+        self.selected.as_ref().map(|selection| selection.index)
+    }
+
+    /// Whether the game has been won or stalemated.
+    pub fn game_over(&self) -> bool {
+        match self.turn.as_ref().unwrap().game() {
+            session::Progression::PlayOn(_) => false,
+            _ => true,
         }
     }
 
     fn select_hexagon(&mut self, coordinate: hexagon::Cube) -> bool {
+        // 0. A finished puzzle can't be played on.
+        if let Some(run) = self.puzzle.as_ref() {
+            if run.status != PuzzleStatus::Solving {
+                jslog!("The puzzle is over.");
+                return false;
+            }
+        }
+
         // 1. Determine that the hexagon coordinate is valid.
         let index = match self.turn
             .as_ref()
@@ -134,6 +264,11 @@ impl Game {
                 },
             };
 
+        if self.template.is_blocked(index) {
+            jslog!("Hexagon at {} is blocked.", &coordinate);
+            return false;
+        }
+
         // 2. Check if a hexagon is already selected or not. That determines how we
         //    treat this hexagon selection.
         if let Some(selection) = self.selected.take() {
@@ -160,6 +295,7 @@ impl Game {
         // 2. Change the danger state of the selected hexagon. Then drop to release `self`.
         detail.set_attacking();
         drop(detail);
+        self.dirty.insert(index);
 
         // 3. Fetch the coordinates of any threatened hexes
         let t_coords: Vec<hexagon::Cube> = self.turn
@@ -191,6 +327,13 @@ impl Game {
                     .grid()
                     .fetch_index(t_coord)
                     .unwrap();
+                (threatened_index, t_coord)
+            })
+            // Blocked and water hexes belong to no one and can't be attacked.
+            .filter(|(threatened_index, _)| !self.template.is_blocked(*threatened_index))
+            .collect::<Vec<(usize, hexagon::Cube)>>()
+            .into_iter()
+            .map(|(threatened_index, t_coord)| {
                 let detail = self.tessellation
                     .as_mut()
                     .unwrap()
@@ -202,6 +345,7 @@ impl Game {
                 Threatened::new(threatened_index, t_coord)
             })
             .collect();
+        self.dirty.extend(threatened.iter().map(|threatened| threatened.index));
 
         // 5. Save the entire selection state.
         let selected = Selected::new(index, coordinate, threatened.as_slice());
@@ -254,18 +398,24 @@ impl Game {
             .unwrap();
 
         // 2. Advance session state.
+        let captured = self.tessellation.as_ref().unwrap().hex(attacked.index).dice();
         let new_state = self.session
             .advance(choice)
             .unwrap()
             .to_owned();
 
         // 3. Update internal game state.
-        let tessellation = grid::generate_tessellation(
-            &self.template, new_state.board(),
-        );
         self.turn = Some(new_state);
-        self.tessellation = Some(tessellation);
+        self.deselect_hexagon(attacker);
+        self.update_tessellation();
         self.selected = None;
+
+        // 4. Judge the attack if a puzzle is being played.
+        if let Some(run) = self.puzzle.as_mut() {
+            run.progress.attacked(captured);
+            run.status = run.puzzle.assess(self.turn.as_ref().unwrap(), &run.progress);
+            jslog!("Puzzle '{}' is {:?}.", run.puzzle.name(), run.status);
+        }
     }
 
     /// Set the attacking hexagons danger level as well as any threatened hexagons to the
@@ -281,6 +431,7 @@ impl Game {
         // 2. Deselect and drop.
         detail.set_safe();
         drop(detail);
+        self.dirty.insert(selection.index);
 
         // 3. Loop through all threatened hexes and set them safe too.
         selection.threatened
@@ -293,20 +444,85 @@ impl Game {
                     .unwrap();
                 
                 detail.set_safe();
+                self.dirty.insert(threatened.index);
             });
     }
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Game {
     pub fn tessellation(&self) -> Tessellation {
         self.tessellation.clone().unwrap()
     }
 
+    /// Indexes of the hexes that changed owner, dice or danger since `clear_dirty` was
+    /// last called, in order. Every hex starts out dirty.
+    pub fn dirty_hexes(&self) -> Vec<u32> {
+        let mut dirty: Vec<u32> = self.dirty.iter().map(|index| *index as u32).collect();
+        dirty.sort();
+        dirty
+    }
+
+    /// Call once the dirty hexes have been redrawn.
+    pub fn clear_dirty(&mut self) {
+        self.dirty.clear();
+    }
+
+    /// Pack the tessellation for JS to read without copying it. See `Tessellation::pack`.
+    pub fn pack(&mut self) {
+        self.tessellation.as_mut().unwrap().pack();
+    }
+
+    pub fn packed_ptr(&self) -> *const i32 {
+        self.current_tessellation().packed_ptr()
+    }
+
+    pub fn packed_len(&self) -> usize {
+        self.current_tessellation().packed_len()
+    }
+
+    /// Lay the board out again to fit a `width` by `height` canvas, such as after the
+    /// window is resized or moved to another screen. The game carries on as it was,
+    /// selection and all. Every hex is dirty afterwards. See `grid::fit_layout`.
+    pub fn relayout(&mut self, width: u32, height: u32, pixel_ratio: f64) {
+        self.set_pixel_ratio(pixel_ratio);
+        self.template = self.template.refit(
+            width, height, grid::FIT_MARGIN, self.pixel_ratio,
+        );
+        let mut tessellation = grid::generate_tessellation(
+            &self.template, self.turn.as_ref().unwrap().board(),
+        );
+        if let Some(selection) = self.selected.as_ref() {
+            if let Some(detail) = tessellation.hex_mut(selection.index) {
+                detail.set_attacking();
+            }
+            for threatened in selection.threatened.iter() {
+                if let Some(detail) = tessellation.hex_mut(threatened.index) {
+                    detail.set_threatened();
+                }
+            }
+        }
+        self.dirty = (0..tessellation.len()).collect();
+        self.tessellation = Some(tessellation);
+    }
+
+    /// How many layout pixels there are to each CSS pixel given to `select_hex_with_pixel`.
+    /// Set it to `window.devicePixelRatio` when the board was laid out for the backing
+    /// store. Ratios that aren't positive are taken as one.
+    pub fn set_pixel_ratio(&mut self, pixel_ratio: f64) {
+        self.pixel_ratio = if pixel_ratio > 0_f64 { pixel_ratio } else { 1_f64 };
+    }
+
+    /// Select the hex under the canvas `pixel`, in CSS pixels. Clicks that miss every hex
+    /// are ignored.
     pub fn select_hex_with_pixel(&mut self, pixel: Point) -> bool {
-        // Convert the pixel (x, y) into a hexagon axial coordinate.
-        let coordinate = pixel.hexagon_axial(self.template.radius());
-        self.select_hexagon(coordinate.into())
+        match self.index_at_pixel(pixel) {
+            Some(index) => self.select_hex_with_index(index),
+            None => {
+                jslog!("No hexagon under {:?}", &pixel);
+                false
+            },
+        }
     }
 
     pub fn current_player_ai(&self) -> bool {
@@ -323,7 +539,7 @@ impl Game {
     pub fn current_player_moves_left(&self) -> u8 {
         let state = self.turn.as_ref().unwrap();
         let moves = state.board().moved();
-        self.session.move_limit().get() - moves
+        self.session.move_limit().get().saturating_sub(moves)
     }
 
     pub fn current_player_dice_captured(&self) -> u8 {
@@ -331,6 +547,28 @@ impl Game {
         *state.board().captured_dice()
     }
 
+    /// The current board and turn in the one line notation of the `notation` module.
+    pub fn position(&self) -> String {
+        let state = self.turn.as_ref().unwrap();
+        Snapshot::from_board(state.board(), &self.template, self.session.move_limit())
+            .to_string()
+    }
+
+    pub fn puzzle_status(&self) -> PuzzleStatus {
+        self.puzzle
+            .as_ref()
+            .map(|run| run.status)
+            .unwrap_or(PuzzleStatus::NotPuzzle)
+    }
+
+    /// What has to be done to solve the puzzle. Empty if not playing a puzzle.
+    pub fn puzzle_description(&self) -> String {
+        self.puzzle
+            .as_ref()
+            .map(|run| run.puzzle.description())
+            .unwrap_or_default()
+    }
+
     /// Convert current state to \n separated string.
     pub fn state_log(&self) -> String {
         let state = self.turn.as_ref().unwrap();
@@ -361,15 +599,71 @@ impl Game {
         if self.ai_players.contains(&curr_player) {
             drop(state);
             let state = self.session.score_with_depth_horizon(self.ai_compute_horizon);
-            let index = handle_ai_turn(state.choices().as_slice());
+            let index = handle_ai_turn(
+                state.choices().as_slice(), state.board(), &self.template,
+            );
             drop(state);
-            let state = self.session.advance(index).unwrap();
-            let tessellation = grid::generate_tessellation(&self.template, state.board());
-            self.turn = Some(state.to_owned());
-            self.tessellation = Some(tessellation);
+            let state = self.session.advance(index).unwrap().to_owned();
+            self.turn = Some(state);
+            self.update_tessellation();
             true
         } else {
             false
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::NEUTRAL;
+    use crate::primitive::Orientation;
+
+    /// Player A's only neighbour is the blocked hex in the middle.
+    const WALLED: &str = "size: 3x1\nA3 # B1\n";
+
+    #[test]
+    fn ai_never_attacks_a_blocked_hex() {
+        let mut game = crate::start_game_from_map(
+            WALLED, Point::default(), 10, Orientation::Pointy, 2, 2, 1, 0, 0, 2,
+        ).unwrap();
+
+        while game.current_player_id() == 1 && game.advance() {
+            assert_eq!(game.tessellation().hex(1).player_number(), NEUTRAL);
+        }
+        assert_eq!(game.tessellation().hex(1).player_number(), NEUTRAL);
+        assert_eq!(game.current_player_id(), 2);
+    }
+
+    #[test]
+    fn attacks_only_dirty_the_hexes_involved() {
+        let mut game = crate::start_game_from_map(
+            "size: 3x1\nA3 B1 A1\n",
+            Point::default(), 10, Orientation::Flat, 2, 1, 1, 0, 0, 2,
+        ).unwrap();
+        game.clear_dirty();
+
+        assert!(!game.select_hex_with_index(0));
+        assert!(game.select_hex_with_index(1));
+        assert_eq!(game.dirty_hexes(), vec![0, 1]);
+        let generated = grid::generate_tessellation(
+            &game.template, game.turn.as_ref().unwrap().board(),
+        );
+        assert_eq!(game.tessellation(), generated);
+    }
+
+    #[test]
+    fn games_are_laid_out_in_the_orientation_asked_for() {
+        for orientation in [Orientation::Pointy, Orientation::Flat].iter() {
+            let map = crate::start_game_from_map(
+                WALLED, Point::default(), 10, *orientation, 2, 1, 1, 0, 0, 2,
+            ).unwrap();
+            assert_eq!(map.tessellation().orientation(), *orientation);
+
+            let position = crate::start_game_from_position(
+                "A3#B1_A_0_0_2", Point::default(), 10, *orientation, 1, 1, 0, 0, 2,
+            ).unwrap();
+            assert_eq!(position.tessellation().orientation(), *orientation);
+        }
+    }
+}
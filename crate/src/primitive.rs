@@ -1,18 +1,19 @@
 //! Tiny stuff from which others are built out of.
 use std::{fmt, ops};
 
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 use dicey_dice::hexagon::Axial;
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Point {
     x: i32,
     y: i32,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Point {
     pub fn new(x: i32, y: i32) -> Self {
         Point { x, y }
@@ -27,20 +28,53 @@ impl Point {
     }
 }
 
+/// Which way up the hexagons sit. A flat top layout is the pointy top layout flipped
+/// over its top left to bottom right diagonal, so rows of pointy hexes become columns of
+/// flat ones.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Orientation {
+    Pointy = 0,
+    Flat = 1,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Orientation::Pointy
+    }
+}
+
 impl Point {
-    pub fn hexagon_axial(&self, radius: u32) -> Axial {
-        let radius = radius as f64;
-        let x = self.x as f64;
-        let y = self.y as f64;
+    /// Swap `x` and `y`.
+    pub fn transpose(&self) -> Self {
+        Point::new(self.y, self.x)
+    }
 
-        let sqrt_3_div_3 = 3_f64.sqrt() / 3_f64;
-        let one_third = 1_f64 / 3_f64;
-        let two_thirds = 2_f64 / 3_f64;
+    /// Bring a point in an `orientation` layout to where it would be in the pointy layout
+    /// or back again. Flipping is its own inverse.
+    pub fn orient(&self, orientation: Orientation) -> Self {
+        match orientation {
+            Orientation::Pointy => *self,
+            Orientation::Flat => self.transpose(),
+        }
+    }
+
+    /// Multiply both coordinates by `by` to the nearest pixel, such as from CSS pixels to
+    /// the backing store.
+    pub fn scale(&self, by: f64) -> Self {
+        (PrecisePoint::from(*self) * by).round()
+    }
+
+    pub fn hexagon_axial(&self, radius: u32, orientation: Orientation) -> Axial {
+        self.axial_parts(radius, orientation).into()
+    }
 
-        let column = ((sqrt_3_div_3 * x - one_third * y) / radius).round() as i32;
-        let row = (two_thirds * y / radius).round() as i32;
-        
-        (column, row).into()
+    /// The axial `(column, row)` of the hexagon this point falls within where the origin
+    /// is the center of the hexagon at `(0, 0)`. The column and row are those of the
+    /// pointy layout whichever the `orientation`.
+    pub fn axial_parts(&self, radius: u32, orientation: Orientation) -> (i32, i32) {
+        PrecisePoint::from(*self).axial_parts(radius as f64, orientation)
     }
 }
 
@@ -50,6 +84,100 @@ impl Default for Point {
     }
 }
 
+/// A point that isn't held to whole pixels. Layout and hit testing are worked out with
+/// these and only rounded to a `Point` once they're ready to be drawn.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct PrecisePoint {
+    x: f64,
+    y: f64,
+}
+
+impl PrecisePoint {
+    pub fn new(x: f64, y: f64) -> Self {
+        PrecisePoint { x, y }
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    /// Nearest whole pixel.
+    pub fn round(&self) -> Point {
+        Point::new(self.x.round() as i32, self.y.round() as i32)
+    }
+
+    pub fn orient(&self, orientation: Orientation) -> Self {
+        match orientation {
+            Orientation::Pointy => *self,
+            Orientation::Flat => PrecisePoint::new(self.y, self.x),
+        }
+    }
+
+    /// Same as `Point::axial_parts` but for a point between pixels.
+    pub fn axial_parts(&self, radius: f64, orientation: Orientation) -> (i32, i32) {
+        let point = self.orient(orientation);
+
+        let sqrt_3_div_3 = 3_f64.sqrt() / 3_f64;
+        let one_third = 1_f64 / 3_f64;
+        let two_thirds = 2_f64 / 3_f64;
+
+        let column = (sqrt_3_div_3 * point.x - one_third * point.y) / radius;
+        let row = two_thirds * point.y / radius;
+
+        cube_round(column, row)
+    }
+}
+
+/// Round a fractional axial coordinate to the hexagon it falls within. Rounding the column
+/// and row on their own goes wrong near the corners so the third cube coordinate is worked
+/// out too and whichever of the three moved the most is fixed up from the other two.
+fn cube_round(column: f64, row: f64) -> (i32, i32) {
+    let third = -column - row;
+    let (mut q, mut r, s) = (column.round(), row.round(), third.round());
+    let (dq, dr, ds) = ((q - column).abs(), (r - row).abs(), (s - third).abs());
+
+    if dq > dr && dq > ds {
+        q = -r - s;
+    } else if dr > ds {
+        r = -q - s;
+    }
+    (q as i32, r as i32)
+}
+
+impl From<Point> for PrecisePoint {
+    fn from(point: Point) -> Self {
+        PrecisePoint::new(point.x as f64, point.y as f64)
+    }
+}
+
+impl ops::Add for PrecisePoint {
+    type Output = PrecisePoint;
+
+    fn add(self, rhs: PrecisePoint) -> Self {
+        PrecisePoint::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl ops::Sub for PrecisePoint {
+    type Output = PrecisePoint;
+
+    fn sub(self, rhs: PrecisePoint) -> Self {
+        PrecisePoint::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl ops::Mul<f64> for PrecisePoint {
+    type Output = PrecisePoint;
+
+    fn mul(self, rhs: f64) -> Self {
+        PrecisePoint::new(self.x * rhs, self.y * rhs)
+    }
+}
+
 /// Cheesy `ops::Add` impl that would have been seen in so many "learn now to program in
 /// rust" tutorials on the web.
 impl ops::Add for Point {
@@ -109,7 +237,7 @@ impl ops::Mul<i32> for Point {
 
 
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Colour {
@@ -139,6 +267,9 @@ pub enum Colour {
     MediumPurple = 17,
     Purple = 18,
     RebeccaPurple = 19,
+
+    /// Water and other terrain nobody can hold.
+    SteelBlue = 20,
 }
 
 impl fmt::Display for Colour {
@@ -164,21 +295,53 @@ impl fmt::Display for Colour {
             Colour::DarkRed => write!(f, "darkred"),
             Colour::Orange => write!(f, "orange"),
             Colour::RebeccaPurple => write!(f, "rebeccapurple"),
+            Colour::SteelBlue => write!(f, "steelblue"),
         }
     }
 }
 
-/*
-#[wasm_bindgen]
 impl Colour {
-    pub fn css_colour(&self) -> String {
-        self.to_string()
+    /// A CSS colour for places stricter than canvas, such as SVG. A few of the names
+    /// aren't real CSS colours so they're spelt out.
+    pub fn css(&self) -> String {
+        match self {
+            Colour::LightRed => "#ff7f7f".to_owned(),
+            Colour::LightBrown => "#c4a484".to_owned(),
+            Colour::DarkBrown => "#5c4033".to_owned(),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Red, green and blue of the CSS colour.
+    pub fn rgb(&self) -> [u8; 3] {
+        match self {
+            Colour::Black => [0, 0, 0],
+            Colour::White => [255, 255, 255],
+            Colour::LightBlue => [173, 216, 230],
+            Colour::Blue => [0, 0, 255],
+            Colour::DarkBlue => [0, 0, 139],
+            Colour::LightGreen => [144, 238, 144],
+            Colour::Green => [0, 128, 0],
+            Colour::DarkGreen => [0, 100, 0],
+            Colour::LightRed => [255, 127, 127],
+            Colour::Red => [255, 0, 0],
+            Colour::DarkRed => [139, 0, 0],
+            Colour::LightBrown => [196, 164, 132],
+            Colour::Brown => [165, 42, 42],
+            Colour::DarkBrown => [92, 64, 51],
+            Colour::LightYellow => [255, 255, 224],
+            Colour::Yellow => [255, 255, 0],
+            Colour::Orange => [255, 165, 0],
+            Colour::MediumPurple => [147, 112, 219],
+            Colour::Purple => [128, 0, 128],
+            Colour::RebeccaPurple => [102, 51, 153],
+            Colour::SteelBlue => [70, 130, 180],
+        }
     }
 }
-*/
 
 /// Convenience struct for grouping together colours for a player.
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Colours {
     pub unselected_colour: Colour,
@@ -197,7 +360,7 @@ impl Colours {
 }
 
 /// Generate the `Colours` pack from a player number. Assumes max num of 6. Wraps around.
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Colours {
     pub (crate) fn from_player_number(player_number: usize) -> Self {
 
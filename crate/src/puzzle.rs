@@ -0,0 +1,450 @@
+//! Puzzles. Each puzzle is a position from the `notation` module with an objective the
+//! player to move must reach within a number of attacks. They're written one per line;
+//!
+//! ```text
+//! First Blood|eliminate B|1|A1A3A1/A1B2A1/A1A1A1_A_0_0_1
+//! ```
+//!
+//! The fields are the name, the objective, the number of attacks allowed and the position.
+//! The objective is one of `eliminate <player letter>`, `capture <dice>` or
+//! `region <hexes>`, the last being the size of the biggest group of touching hexes. An
+//! optional fifth field rates the difficulty from 1 to 5, such as on generated puzzles.
+use std::{error, fmt};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use dicey_dice::{session, hexagon, game};
+
+use crate::analysis;
+use crate::grid::Template;
+use crate::map;
+use crate::notation::{NotationError, Snapshot};
+use crate::play;
+use crate::primitive::{Orientation, Point};
+
+/// The puzzles that ship with the game. The tests run `verify_bundled` to prove each one
+/// can be solved.
+pub const BUNDLED: &[&str] = &[
+    "First Blood|eliminate B|1|A1A3A1/A1B2A1/A1A1A1_A_0_0_1",
+    "Two Step|eliminate B|2|A4B1B2/A1A1A1/A1A1A1_A_0_0_2",
+    "Dice Haul|capture 5|2|A5B4A1/A1B1A1/A1A1A1_A_0_0_2",
+    "Join Up|region 9|2|A1A1A1/B1A2B1/A1A1A2_A_0_0_2",
+];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Objective {
+    /// Take every hex of this player.
+    Eliminate(usize),
+
+    /// Capture at least this many dice.
+    CaptureDice(u32),
+
+    /// Own a group of touching hexes at least this big.
+    OwnRegion(u32),
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Objective::Eliminate(player) => {
+                write!(f, "eliminate {}", map::player_letter(*player))
+            },
+            Objective::CaptureDice(dice) => write!(f, "capture {}", dice),
+            Objective::OwnRegion(hexes) => write!(f, "region {}", hexes),
+        }
+    }
+}
+
+/// How a puzzle attempt is going.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PuzzleStatus {
+    NotPuzzle = 0,
+    Solving = 1,
+    Solved = 2,
+    Failed = 3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleError {
+    Format(String),
+    Objective(String),
+    Attacks(String),
+    Difficulty(String),
+    Position(NotationError),
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleError::Format(line) => {
+                write!(
+                    f,
+                    "Puzzle '{}' should be name|objective|attacks|position|difficulty with \
+                     the difficulty optional",
+                    line,
+                )
+            },
+            PuzzleError::Objective(objective) => {
+                write!(f, "Unknown objective '{}'", objective)
+            },
+            PuzzleError::Attacks(attacks) => {
+                write!(f, "Attacks '{}' should be a number above zero", attacks)
+            },
+            PuzzleError::Difficulty(difficulty) => {
+                write!(f, "Difficulty '{}' should be a number from 1 to 5", difficulty)
+            },
+            PuzzleError::Position(e) => write!(f, "Bad position. {}", e),
+        }
+    }
+}
+
+impl error::Error for PuzzleError {}
+
+fn parse_objective(text: &str) -> Result<Objective, PuzzleError> {
+    let bad = || PuzzleError::Objective(text.to_owned());
+    let mut parts = text.split_whitespace();
+    let kind = parts.next().ok_or_else(bad)?;
+    let value = parts.next().ok_or_else(bad)?;
+    if parts.next().is_some() {
+        return Err(bad());
+    }
+
+    match kind {
+        "eliminate" => {
+            let mut letters = value.chars();
+            let player = letters.next().and_then(map::player_number).ok_or_else(bad)?;
+            if letters.next().is_some() {
+                return Err(bad());
+            }
+            Ok(Objective::Eliminate(player))
+        },
+        "capture" => value.parse().map(Objective::CaptureDice).map_err(|_| bad()),
+        "region" => value.parse().map(Objective::OwnRegion).map_err(|_| bad()),
+        _ => Err(bad()),
+    }
+}
+
+/// A puzzle attempt so far.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Progress {
+    attacks: u8,
+    captured: u32,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Progress::default()
+    }
+
+    /// Record an attack that took a hex holding `dice`.
+    pub fn attacked(&mut self, dice: u8) {
+        self.attacks += 1;
+        self.captured += dice as u32;
+    }
+
+    pub fn attacks(&self) -> u8 {
+        self.attacks
+    }
+
+    pub fn captured(&self) -> u32 {
+        self.captured
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    name: String,
+    objective: Objective,
+    attacks: u8,
+    snapshot: Snapshot,
+
+    /// The board of the `snapshot`, laid out only for which hexes touch which.
+    template: Template,
+
+    /// From 1 for the easiest up to 5, if it's been rated.
+    difficulty: Option<u8>,
+}
+
+impl Puzzle {
+    pub fn new(name: &str, objective: Objective, attacks: u8, snapshot: Snapshot) -> Self {
+        let template = snapshot.template(Point::default(), 1, Orientation::Pointy);
+        Puzzle {
+            name: name.to_owned(),
+            objective,
+            attacks,
+            snapshot,
+            template,
+            difficulty: None,
+        }
+    }
+
+    /// Rate the puzzle from 1 to 5. Anything outside that is clamped.
+    pub fn with_difficulty(mut self, difficulty: u8) -> Self {
+        self.difficulty = Some(difficulty.max(1).min(5));
+        self
+    }
+
+    pub fn parse(line: &str) -> Result<Self, PuzzleError> {
+        let fields: Vec<&str> = line.trim().split('|').collect();
+        if fields.len() != 4 && fields.len() != 5 {
+            return Err(PuzzleError::Format(line.to_owned()));
+        }
+
+        let objective = parse_objective(fields[1].trim())?;
+        let attacks: u8 = fields[2]
+            .trim()
+            .parse()
+            .ok()
+            .filter(|attacks| *attacks > 0)
+            .ok_or_else(|| PuzzleError::Attacks(fields[2].to_owned()))?;
+        let snapshot = Snapshot::parse(fields[3]).map_err(PuzzleError::Position)?;
+        let puzzle = Puzzle::new(fields[0].trim(), objective, attacks, snapshot);
+
+        match fields.get(4) {
+            Some(difficulty) => difficulty
+                .trim()
+                .parse()
+                .ok()
+                .filter(|difficulty| *difficulty >= 1 && *difficulty <= 5)
+                .map(|difficulty| puzzle.with_difficulty(difficulty))
+                .ok_or_else(|| PuzzleError::Difficulty(difficulty.to_string())),
+            None => Ok(puzzle),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn objective(&self) -> Objective {
+        self.objective
+    }
+
+    pub fn attacks(&self) -> u8 {
+        self.attacks
+    }
+
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    pub fn difficulty(&self) -> Option<u8> {
+        self.difficulty
+    }
+
+    /// The player solving the puzzle.
+    pub fn player(&self) -> usize {
+        self.snapshot.current()
+    }
+
+    /// What the player has to do in plain words.
+    pub fn description(&self) -> String {
+        let goal = match self.objective {
+            Objective::Eliminate(player) => format!("Eliminate Player{}", player),
+            Objective::CaptureDice(dice) => format!("Capture {} dice", dice),
+            Objective::OwnRegion(hexes) => {
+                format!("Own a group of {} touching hexes", hexes)
+            },
+        };
+        let attacks = if self.attacks == 1 { "attack" } else { "attacks" };
+        format!("{} within {} {}.", goal, self.attacks, attacks)
+    }
+
+    fn objective_met(&self, board: &game::Board, progress: &Progress) -> bool {
+        match self.objective {
+            Objective::Eliminate(player) => board
+                .grid()
+                .iter()
+                .all(|hex| *hex.data().owner().number() != player),
+            Objective::CaptureDice(dice) => progress.captured >= dice,
+            Objective::OwnRegion(hexes) => {
+                analysis::analyse(board, &self.template)
+                    .iter()
+                    .find(|stats| stats.player == self.player())
+                    .map(|stats| stats.largest_region >= hexes)
+                    .unwrap_or(false)
+            },
+        }
+    }
+
+    /// Judge the attempt at `state` after the `progress` made so far. The objective is
+    /// checked first since the last allowed attack can hand the turn over.
+    pub fn assess(&self, state: &session::State, progress: &Progress) -> PuzzleStatus {
+        if self.objective_met(state.board(), progress) {
+            return PuzzleStatus::Solved;
+        }
+
+        let game_over = match state.game() {
+            session::Progression::PlayOn(_) => false,
+            _ => true,
+        };
+        let player_moved_on = *state.board().players().current().number() != self.player();
+
+        if game_over || player_moved_on || progress.attacks >= self.attacks {
+            PuzzleStatus::Failed
+        } else {
+            PuzzleStatus::Solving
+        }
+    }
+
+    /// Search every line of attacks for one that solves the puzzle. Returns the choice
+    /// indexes to play from the start. Attacks are decided without chance so a found
+    /// solution always works.
+    pub fn solve(&self) -> Result<Option<Vec<usize>>, String> {
+        Ok(self.search(1)?.solutions.pop())
+    }
+
+    /// Search the lines of attacks, stopping once `cap` solutions are found.
+    pub fn search(&self, cap: usize) -> Result<Search, String> {
+        let mut search = Search::default();
+        self.explore(&mut Vec::new(), Progress::new(), cap, &mut search)?;
+        Ok(search)
+    }
+
+    /// Sessions can't be copied so each branch is replayed from the start.
+    fn replay(&self, line: &[usize]) -> Result<session::Session, String> {
+        let mut session = self.snapshot.session()?;
+        for choice in line {
+            session.advance(*choice).map_err(|e| e.to_string())?;
+        }
+        Ok(session)
+    }
+
+    fn explore(
+        &self, line: &mut Vec<usize>, progress: Progress, cap: usize, search: &mut Search,
+    ) -> Result<(), String> {
+        search.nodes += 1;
+        search.depth = search.depth.max(line.len());
+
+        let session = self.replay(line)?;
+        let state = session.current_turn();
+
+        if !line.is_empty() {
+            match self.assess(state, &progress) {
+                PuzzleStatus::Solved => {
+                    search.solutions.push(line.clone());
+                    return Ok(());
+                },
+                PuzzleStatus::Failed => return Ok(()),
+                _ => (),
+            }
+        }
+
+        // Blocked hexes can't be taken so there's no point looking down those lines.
+        let board = state.board();
+        let attacks: Vec<(usize, u8)> = state
+            .choices()
+            .iter()
+            .enumerate()
+            .filter(|(_, choice)| {
+                let blocked = |index| self.snapshot.is_blocked(index);
+                !play::attacks_blocked(choice, board, blocked)
+            })
+            .filter_map(|(index, choice)| match choice.action() {
+                game::Action::Attack(_, to_hex, _, _) => {
+                    let dice = dice_at(state.board(), *to_hex)?;
+                    Some((index, dice))
+                },
+                _ => None,
+            })
+            .collect();
+        drop(session);
+
+        for (index, dice) in attacks {
+            if search.solutions.len() >= cap {
+                break;
+            }
+            let mut next = progress;
+            next.attacked(dice);
+            line.push(index);
+            self.explore(line, next, cap, search)?;
+            line.pop();
+        }
+
+        Ok(())
+    }
+}
+
+/// What searching a puzzle turned up.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Search {
+    /// Each solution is the choice indexes to play from the start.
+    pub solutions: Vec<Vec<usize>>,
+
+    /// Number of positions looked at.
+    pub nodes: usize,
+
+    /// Most attacks looked ahead.
+    pub depth: usize,
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}|{}|{}|{}", self.name, self.objective, self.attacks, self.snapshot)?;
+        match self.difficulty {
+            Some(difficulty) => write!(f, "|{}", difficulty),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Dice on the hex at `coordinate`.
+pub (crate) fn dice_at(board: &game::Board, coordinate: hexagon::Cube) -> Option<u8> {
+    let index = board.grid().fetch_index(coordinate).ok()?;
+    board.grid().iter().nth(index).map(|hex| hex.data().dice())
+}
+
+pub fn bundled() -> Vec<Puzzle> {
+    BUNDLED
+        .iter()
+        .map(|line| Puzzle::parse(line).unwrap())
+        .collect()
+}
+
+/// Solve every bundled puzzle. Each entry is the puzzle name with its solution, or why
+/// it couldn't be solved.
+pub fn verify_bundled() -> Vec<(String, Result<Vec<usize>, String>)> {
+    BUNDLED
+        .iter()
+        .map(|line| match Puzzle::parse(line) {
+            Ok(puzzle) => {
+                let solution = puzzle
+                    .solve()
+                    .and_then(|found| found.ok_or_else(|| "No solution".to_owned()));
+                (puzzle.name().to_owned(), solution)
+            },
+            Err(e) => (line.to_string(), Err(e.to_string())),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_puzzles_are_solvable() {
+        for (name, outcome) in verify_bundled() {
+            assert!(outcome.is_ok(), "{} can't be solved. {:?}", name, outcome);
+        }
+    }
+
+    #[test]
+    fn bundled_puzzles_round_trip() {
+        for (puzzle, line) in bundled().iter().zip(BUNDLED) {
+            assert_eq!(&puzzle.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn difficulty_is_kept() {
+        let line = "Hard|eliminate B|1|A1A3A1/A1B2A1/A1A1A1_A_0_0_1|4";
+        let puzzle = Puzzle::parse(line).unwrap();
+        assert_eq!(puzzle.difficulty(), Some(4));
+        assert_eq!(puzzle.to_string(), line);
+        assert!(Puzzle::parse("Hard|eliminate B|1|A1B1_A_0_0_1|9").is_err());
+    }
+}
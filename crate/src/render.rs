@@ -0,0 +1,279 @@
+//! Backend neutral drawing. A `Tessellation` is laid out into a list of draw commands once
+//! here and each backend, such as the canvas or SVG, only has to know how to carry out a
+//! handful of commands.
+use crate::dice::{self, DiceTemplate};
+use crate::grid::Tessellation;
+use crate::hex::Detail;
+use crate::map::player_letter;
+use crate::primitive::Point;
+use crate::theme::Theme;
+
+/// How a shape is coloured in. Colours are any CSS colour.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paint {
+    pub fill: Option<String>,
+    pub stroke: Option<String>,
+    pub line_width: u32,
+}
+
+impl Paint {
+    pub fn fill(colour: &str) -> Self {
+        Paint { fill: Some(colour.to_owned()), stroke: None, line_width: 1 }
+    }
+
+    pub fn stroke(colour: &str, line_width: u32) -> Self {
+        Paint { fill: None, stroke: Some(colour.to_owned()), line_width }
+    }
+
+    pub fn fill_and_stroke(fill: &str, stroke: &str) -> Self {
+        Paint {
+            fill: Some(fill.to_owned()),
+            stroke: Some(stroke.to_owned()),
+            line_width: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Closed shape through all the points.
+    Polygon { points: Vec<Point>, paint: Paint },
+    Rect { top_left: Point, width: i32, height: i32, paint: Paint },
+    Circle { center: Point, radius: u32, paint: Paint },
+
+    /// Text centered on `position`.
+    Text { position: Point, text: String, paint: Paint },
+}
+
+fn hex_points(detail: &Detail) -> Vec<Point> {
+    (0..6).map(|corner| detail.point(corner)).collect()
+}
+
+fn die(commands: &mut Vec<Command>, theme: &Theme, template: &DiceTemplate, dots: u8) {
+    commands.push(Command::Rect {
+        top_left: Point::new(template.x(), template.y()),
+        width: template.width(),
+        height: template.height(),
+        paint: Paint::fill_and_stroke(theme.die(), theme.dot()),
+    });
+    commands.extend(
+        dice::pips(dots)
+            .iter()
+            .map(|position| template.dot(*position))
+            .map(|dot| Command::Circle {
+                center: Point::new(dot.x(), dot.y()),
+                radius: dot.radius(),
+                paint: Paint::fill(theme.dot()),
+            })
+    );
+}
+
+/// Commands for a single hex with its dice. Water is filled without dice, other blocked
+/// hexes are only outlined and hexes that aren't on the board aren't drawn at all.
+pub fn hex(detail: &Detail, radius: u32, theme: &Theme) -> Vec<Command> {
+    if detail.absent() {
+        return Vec::new();
+    }
+    if detail.water() {
+        return vec![Command::Polygon {
+            points: hex_points(detail),
+            paint: Paint::fill_and_stroke(&detail.colour().css(), theme.outline()),
+        }];
+    }
+    if detail.blocked() {
+        return vec![Command::Polygon {
+            points: hex_points(detail),
+            paint: Paint::stroke(theme.blocked(), 1),
+        }];
+    }
+
+    let mut commands = vec![Command::Polygon {
+        points: hex_points(detail),
+        paint: Paint::fill_and_stroke(&detail.colour().css(), theme.outline()),
+    }];
+    dice::stack(detail.dice())
+        .iter()
+        .zip(1..)
+        .for_each(|(position, dots)| {
+            let template = DiceTemplate::new(&detail.center(), radius, *position);
+            die(&mut commands, theme, &template, dots);
+        });
+    commands
+}
+
+/// Commands for the whole board, drawn in order.
+pub fn board(tessellation: &Tessellation, theme: &Theme) -> Vec<Command> {
+    (0..tessellation.len())
+        .map(|index| tessellation.hex(index))
+        .flat_map(|detail| hex(&detail, tessellation.radius(), theme))
+        .collect()
+}
+
+/// Commands for only the hexes at `indexes`, such as those that have changed.
+pub fn hexes(tessellation: &Tessellation, indexes: &[u32], theme: &Theme) -> Vec<Command> {
+    indexes
+        .iter()
+        .map(|index| tessellation.hex(*index as usize))
+        .flat_map(|detail| hex(&detail, tessellation.radius(), theme))
+        .collect()
+}
+
+/// Outline the hexes at `indexes` in the highlight colour.
+pub fn highlights(
+    tessellation: &Tessellation, indexes: &[u32], theme: &Theme,
+) -> Vec<Command> {
+    indexes
+        .iter()
+        .map(|index| tessellation.hex(*index as usize))
+        .map(|detail| Command::Polygon {
+            points: hex_points(&detail),
+            paint: Paint::stroke(theme.highlight(), 4),
+        })
+        .collect()
+}
+
+/// Width of a hex in a text diagram, in characters, with the space between hexes.
+const CELL_WIDTH: i32 = 4;
+
+/// Text at a character position of a diagram.
+fn label(text: String, x: i32, y: i32, paint: Paint) -> Command {
+    Command::Text { position: Point::new(x, y), text, paint }
+}
+
+/// The board as text for a character grid, where positions count characters across and
+/// lines down. Each hex is its owner letter and dice, `A3`, with the column and row
+/// numbers around the edge. The attacking hex is marked with `*`, the hexes it threatens
+/// with `!`, blocked hexes are `##`, water is `~~` and those not on the board are left out.
+/// Odd rows are shunted half a hex to the right just like the drawn board.
+pub fn diagram(tessellation: &Tessellation, theme: &Theme) -> Vec<Command> {
+    let columns = tessellation.columns() as i32;
+    let numbers = Paint::fill(theme.outline());
+    // Labels are three characters wide so each is centered one in from where it starts.
+    let mut commands: Vec<Command> = (0..columns)
+        .map(|column| {
+            let x = CELL_WIDTH * (column + 1) + 1;
+            label(format!("{:>3}", column), x, 0, numbers.clone())
+        })
+        .collect();
+
+    for row in 0..tessellation.rows() as i32 {
+        commands.push(label(format!("{:>3}", row), 1, row + 1, numbers.clone()));
+        let shunt = if row % 2 == 1 { CELL_WIDTH / 2 } else { 0 };
+        commands.extend(
+            (0..columns)
+                .map(|column| (column, tessellation.hex((row * columns + column) as usize)))
+                .filter(|(_, detail)| !detail.absent())
+                .map(|(column, detail)| {
+                    let x = CELL_WIDTH * (column + 1) + shunt + 1;
+                    let (text, paint) = if detail.water() {
+                        (" ~~".to_owned(), Paint::fill(&detail.colour().css()))
+                    } else if detail.blocked() {
+                        (" ##".to_owned(), Paint::fill(theme.blocked()))
+                    } else {
+                        let mark = if detail.attacking() {
+                            '*'
+                        } else if detail.threatened() {
+                            '!'
+                        } else {
+                            ' '
+                        };
+                        let letter = player_letter(detail.player_number());
+                        (
+                            format!("{}{}{}", mark, letter, detail.dice()),
+                            Paint::fill(&detail.colour().css()),
+                        )
+                    };
+                    label(text, x, row + 1, paint)
+                })
+        );
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive::Orientation;
+
+    /// Backend that writes down what it was asked to draw, a line per command.
+    #[derive(Default)]
+    struct Recording {
+        log: Vec<String>,
+    }
+
+    impl Recording {
+        fn execute(&mut self, command: &Command) {
+            let entry = match command {
+                Command::Polygon { points, .. } => format!("polygon {}", points.len()),
+                Command::Rect { width, height, .. } => format!("rect {}x{}", width, height),
+                Command::Circle { .. } => "circle".to_owned(),
+                Command::Text { position, text, .. } => {
+                    format!("text {},{} {}", position.x(), position.y(), text.trim())
+                },
+            };
+            self.log.push(entry);
+        }
+
+        fn count(&self, kind: &str) -> usize {
+            self.log.iter().filter(|entry| entry.starts_with(kind)).count()
+        }
+    }
+
+    fn record(commands: &[Command]) -> Recording {
+        let mut recording = Recording::default();
+        commands.iter().for_each(|command| recording.execute(command));
+        recording
+    }
+
+    fn crossing() -> Tessellation {
+        crate::start_game_from_position(
+            "A2B1A3/B2#A1/A1A1B1_A_0_0_3",
+            Point::default(), 10, Orientation::Flat, 1, 1, 0, 0, 1,
+        )
+            .unwrap()
+            .tessellation()
+    }
+
+    #[test]
+    fn draws_a_die_for_each_of_the_dice() {
+        let tessellation = crossing();
+        let theme = Theme::default();
+        let recording = record(&hex(&tessellation.hex(2), tessellation.radius(), &theme));
+        assert_eq!(recording.count("polygon 6"), 1);
+        assert_eq!(recording.count("rect"), 3);
+        // A die for one, two and three.
+        assert_eq!(recording.count("circle"), 6);
+    }
+
+    #[test]
+    fn only_outlines_blocked_hexes() {
+        let tessellation = crossing();
+        let theme = Theme::default();
+        let commands = hex(&tessellation.hex(4), tessellation.radius(), &theme);
+        assert_eq!(record(&commands).log, vec!["polygon 6"]);
+        match &commands[0] {
+            Command::Polygon { paint, .. } => {
+                assert_eq!(paint, &Paint::stroke(theme.blocked(), 1));
+            },
+            command => panic!("expected an outline, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn board_draws_every_hex() {
+        let tessellation = crossing();
+        let recording = record(&board(&tessellation, &Theme::default()));
+        assert_eq!(recording.count("polygon"), tessellation.len());
+        assert_eq!(recording.count("text"), 0);
+    }
+
+    #[test]
+    fn diagram_labels_every_hex_and_edge() {
+        let recording = record(&diagram(&crossing(), &Theme::default()));
+        // Three column numbers, three row numbers and nine hexes.
+        assert_eq!(recording.count("text"), 15);
+        assert!(recording.log.contains(&"text 5,1 A2".to_owned()));
+        assert!(recording.log.contains(&"text 11,2 ##".to_owned()));
+        assert!(recording.log.contains(&"text 1,3 2".to_owned()));
+    }
+}
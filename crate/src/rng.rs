@@ -0,0 +1,42 @@
+//! Seeded random numbers for dealing boards and growing terrain.
+
+/// Small xorshift generator. Good enough for rolling boards and dependency free. The same
+/// seed always gives the same numbers so boards can be dealt again exactly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Zero would get stuck.
+        Rng(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Number from `low` to `high` inclusive.
+    pub fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next_u64() % (high - low + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_repeat_for_the_same_seed() {
+        let (mut first, mut second) = (Rng::new(0), Rng::new(0));
+        for _ in 0..100 {
+            let number = first.range(2, 5);
+            assert!((2..=5).contains(&number));
+            assert_eq!(number, second.range(2, 5));
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Standalone SVG documents of a board. Drawn from the same commands as the canvas so an
+//! export looks just like the game, only without depending on pixels.
+use std::fmt::Write;
+
+use crate::grid::Tessellation;
+use crate::primitive::Point;
+use crate::render::{self, Command, Paint};
+use crate::theme::Theme;
+
+/// Space left around the board.
+const MARGIN: i32 = 4;
+
+fn paint_attributes(paint: &Paint) -> String {
+    format!(
+        "fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"",
+        paint.fill.as_ref().map(|s| s.as_str()).unwrap_or("none"),
+        paint.stroke.as_ref().map(|s| s.as_str()).unwrap_or("none"),
+        paint.line_width,
+    )
+}
+
+/// Text made safe to put between SVG tags.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Write a single command as an SVG element.
+fn element(svg: &mut String, command: &Command) {
+    match command {
+        Command::Polygon { points, paint } => {
+            let points: Vec<String> = points
+                .iter()
+                .map(|point| format!("{},{}", point.x(), point.y()))
+                .collect();
+            writeln!(
+                svg,
+                "<polygon points=\"{}\" {}/>",
+                points.join(" "), paint_attributes(paint),
+            ).unwrap();
+        },
+        Command::Rect { top_left, width, height, paint } => {
+            writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" {}/>",
+                top_left.x(), top_left.y(), width, height, paint_attributes(paint),
+            ).unwrap();
+        },
+        Command::Circle { center, radius, paint } => {
+            writeln!(
+                svg,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>",
+                center.x(), center.y(), radius, paint_attributes(paint),
+            ).unwrap();
+        },
+        Command::Text { position, text, paint } => {
+            writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" \
+                 dominant-baseline=\"middle\" {}>{}</text>",
+                position.x(), position.y(), paint_attributes(paint), escape(text),
+            ).unwrap();
+        },
+    }
+}
+
+/// Bottom right most point a command draws to.
+fn far_corner(command: &Command) -> Point {
+    match command {
+        Command::Polygon { points, .. } => points
+            .iter()
+            .fold(Point::default(), |far, point| {
+                Point::new(far.x().max(point.x()), far.y().max(point.y()))
+            }),
+        Command::Rect { top_left, width, height, .. } => {
+            *top_left + Point::new(*width, *height)
+        },
+        Command::Circle { center, radius, .. } => {
+            *center + Point::new(*radius as i32, *radius as i32)
+        },
+        Command::Text { position, .. } => *position,
+    }
+}
+
+/// Width and height that fit every shape with a margin all round.
+fn extent(commands: &[Command]) -> (i32, i32) {
+    commands
+        .iter()
+        .map(far_corner)
+        .fold((0, 0), |(width, height), far| {
+            (width.max(far.x() + MARGIN), height.max(far.y() + MARGIN))
+        })
+}
+
+/// Draw the whole `tessellation` as an SVG document.
+pub fn render(tessellation: &Tessellation, theme: &Theme) -> String {
+    let commands = render::board(tessellation, theme);
+    let (width, height) = extent(&commands);
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+         viewBox=\"0 0 {0} {1}\">",
+        width, height,
+    ).unwrap();
+    commands.iter().for_each(|command| element(&mut svg, command));
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_text() {
+        let mut svg = String::new();
+        let command = Command::Text {
+            position: Point::new(1, 2),
+            text: "<A & B>".to_owned(),
+            paint: Paint::fill("black"),
+        };
+        element(&mut svg, &command);
+        assert!(svg.contains(">&lt;A &amp; B&gt;</text>"), "{}", svg);
+    }
+}
@@ -0,0 +1,135 @@
+//! Random terrain for generated boards. Lakes of water are grown across the land a hex at
+//! a time. A hex is only flooded when the land left over is still in one piece so no
+//! player can be cut off from the rest of the board.
+use std::collections::{HashSet, VecDeque};
+
+use crate::grid::Template;
+use crate::rng::Rng;
+
+/// Smallest and largest number of hexes in a single lake.
+const LAKE_SIZE: (u64, u64) = (2, 5);
+
+/// Tries at growing each lake before giving up on it.
+const ATTEMPTS: usize = 20;
+
+/// Indexes of hexes to flood for `count` lakes on the `template`. Only hexes that aren't
+/// blocked already are land. Fewer or smaller lakes are returned on boards too cramped to
+/// fit them.
+pub fn lakes(template: &Template, count: u32, rng: &mut Rng) -> Vec<usize> {
+    let mut land: Vec<bool> = (0..(template.columns() * template.rows()) as usize)
+        .map(|index| !template.is_blocked(index))
+        .collect();
+    let mut water = Vec::new();
+
+    for _ in 0..count {
+        let size = rng.range(LAKE_SIZE.0, LAKE_SIZE.1) as usize;
+        let mut lake: Vec<usize> = Vec::new();
+
+        for _ in 0..ATTEMPTS {
+            if lake.len() == size {
+                break;
+            }
+            let shore: Vec<usize> = if lake.is_empty() {
+                (0..land.len()).filter(|index| land[*index]).collect()
+            } else {
+                lake.iter()
+                    .flat_map(|index| template.neighbours(*index))
+                    .filter(|index| land[*index])
+                    .collect()
+            };
+            if shore.is_empty() {
+                break;
+            }
+
+            let index = shore[rng.range(0, shore.len() as u64 - 1) as usize];
+            land[index] = false;
+            if connected(template, &land) {
+                lake.push(index);
+            } else {
+                land[index] = true;
+            }
+        }
+        water.extend(lake);
+    }
+    water
+}
+
+/// Whether every land hex can be walked to from every other.
+fn connected(template: &Template, land: &[bool]) -> bool {
+    let start = match land.iter().position(|on_land| *on_land) {
+        Some(start) => start,
+        None => return true,
+    };
+
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::new();
+    seen.insert(start);
+    queue.push_back(start);
+    while let Some(index) = queue.pop_front() {
+        template
+            .neighbours(index)
+            .filter(|next| land[*next])
+            .for_each(|next| if seen.insert(next) {
+                queue.push_back(next);
+            });
+    }
+    seen.len() == land.iter().filter(|on_land| **on_land).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{self, Shape};
+    use crate::primitive::{Orientation, Point};
+    use crate::GameSettings;
+
+    const SHAPES: [Shape; 3] = [Shape::Rectangle, Shape::Hexagon, Shape::Triangle];
+
+    #[test]
+    fn lakes_leave_the_land_in_one_piece() {
+        for shape in SHAPES.iter().cloned() {
+            for side in 3..=8 {
+                for seed in 1..=20 {
+                    let mut land = grid::shape_mask(shape, side, side);
+                    let mut template = grid::generate_template(
+                        side, side, Point::default(), 10, Orientation::Pointy,
+                    );
+                    template.mask(&land);
+                    for index in lakes(&template, 3, &mut Rng::new(seed)) {
+                        assert!(land[index], "{} flooded twice or off the board", index);
+                        land[index] = false;
+                    }
+                    let message = format!("{:?} {} by {} seed {}", shape, side, side, seed);
+                    assert!(connected(&template, &land), "{}", message);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn water_is_never_threatened() {
+        for shape in SHAPES.iter().cloned() {
+            for seed in 1..=10 {
+                let settings = GameSettings::new(6, Point::default(), 10)
+                    .with_players(1, 1, 1, 0)
+                    .with_ai_compute_horizon(1)
+                    .with_shape(shape)
+                    .with_lakes(3)
+                    .with_seed(seed);
+                let mut game = crate::start_new_game(&settings);
+                for index in 0..36 {
+                    game.select_hex_with_index(index);
+                    if game.selected_index() != Some(index) {
+                        continue;
+                    }
+                    let tessellation = game.tessellation();
+                    (0..tessellation.len())
+                        .map(|target| tessellation.hex(target))
+                        .filter(|detail| detail.threatened())
+                        .for_each(|detail| assert!(!detail.blocked(), "{:?}", detail));
+                    game.select_hex_with_index(index);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Plain text from draw commands, for logs, bug reports and the terminal. Positions are
+//! taken as characters across and lines down, so only the `Text` commands of a layout
+//! meant for a character grid, such as `render::diagram`, come out as anything useful.
+use crate::render::Command;
+
+/// Lay the text of `commands` out line by line. Shapes can't be drawn in characters and
+/// are skipped, as is colour. Trailing spaces are trimmed from every line.
+pub fn render(commands: &[Command]) -> String {
+    let mut lines: Vec<Vec<char>> = Vec::new();
+    commands.iter().for_each(|command| {
+        if let Command::Text { position, text, .. } = command {
+            if position.y() < 0 {
+                return;
+            }
+            let y = position.y() as usize;
+            if lines.len() <= y {
+                lines.resize(y + 1, Vec::new());
+            }
+            let line = &mut lines[y];
+            let start = (position.x() - text.chars().count() as i32 / 2).max(0) as usize;
+            text.chars().enumerate().for_each(|(offset, c)| {
+                if line.len() <= start + offset {
+                    line.resize(start + offset + 1, ' ');
+                }
+                line[start + offset] = c;
+            });
+        }
+    });
+
+    lines
+        .iter()
+        .map(|line| {
+            let mut line: String = line.iter().collect();
+            line.truncate(line.trim_end().len());
+            line.push('\n');
+            line
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitive::Point;
+    use crate::render::Paint;
+
+    fn text(x: i32, y: i32, text: &str) -> Command {
+        Command::Text {
+            position: Point::new(x, y),
+            text: text.to_owned(),
+            paint: Paint::fill("black"),
+        }
+    }
+
+    #[test]
+    fn centers_text_on_its_position() {
+        let commands = vec![text(1, 0, "A3"), text(5, 0, "B12"), text(2, 2, "x")];
+        assert_eq!(render(&commands), "A3  B12\n\n  x\n");
+    }
+
+    #[test]
+    fn skips_shapes() {
+        let commands = vec![
+            Command::Circle {
+                center: Point::new(0, 0),
+                radius: 2,
+                paint: Paint::fill("red"),
+            },
+            text(0, 0, "A"),
+        ];
+        assert_eq!(render(&commands), "A\n");
+    }
+}
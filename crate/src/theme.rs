@@ -0,0 +1,117 @@
+//! Colours used when drawing that don't come from the players.
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Red, green and blue of a CSS colour for backends that can't take CSS, such as WebGL.
+/// Only `#rgb`, `#rrggbb` and a few names are understood. Anything else is an error rather
+/// than some colour picked in its place.
+pub fn rgb(css: &str) -> Result<[u8; 3], String> {
+    parse_rgb(css)
+        .ok_or_else(|| format!("Can't draw the colour '{}'. Use #rgb or #rrggbb.", css))
+}
+
+fn parse_rgb(css: &str) -> Option<[u8; 3]> {
+    let css = css.trim();
+    if css.starts_with('#') && css.is_ascii() {
+        let digits = &css[1..];
+        let channel = |text: &str| u8::from_str_radix(text, 16).ok();
+        return match digits.len() {
+            3 => Some([
+                channel(&digits[0..1])? * 17,
+                channel(&digits[1..2])? * 17,
+                channel(&digits[2..3])? * 17,
+            ]),
+            6 => Some([
+                channel(&digits[0..2])?,
+                channel(&digits[2..4])?,
+                channel(&digits[4..6])?,
+            ]),
+            _ => None,
+        };
+    }
+
+    match css {
+        "black" => Some([0, 0, 0]),
+        "white" => Some([255, 255, 255]),
+        "silver" => Some([192, 192, 192]),
+        "grey" | "gray" => Some([128, 128, 128]),
+        "gold" => Some([255, 215, 0]),
+        _ => None,
+    }
+}
+
+/// Each colour is any CSS colour.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    die: String,
+    dot: String,
+    outline: String,
+    blocked: String,
+    highlight: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::new("white", "black")
+    }
+}
+
+impl Theme {
+    pub fn die(&self) -> &str {
+        &self.die
+    }
+
+    pub fn dot(&self) -> &str {
+        &self.dot
+    }
+
+    /// Edge of every playable hex.
+    pub fn outline(&self) -> &str {
+        &self.outline
+    }
+
+    /// Blocked hexes are only drawn as an outline in this colour.
+    pub fn blocked(&self) -> &str {
+        &self.blocked
+    }
+
+    /// Edge of hexes picked out, such as the ones a tutorial step is about.
+    pub fn highlight(&self) -> &str {
+        &self.highlight
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Theme {
+    /// Dice in the given colours with the usual outlines.
+    pub fn new(die: &str, dot: &str) -> Self {
+        Theme {
+            die: die.to_owned(),
+            dot: dot.to_owned(),
+            outline: "black".to_owned(),
+            blocked: "silver".to_owned(),
+            highlight: "gold".to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_hex_colours_and_names() {
+        assert_eq!(rgb("#fff"), Ok([255, 255, 255]));
+        assert_eq!(rgb("#4682b4"), Ok([70, 130, 180]));
+        assert_eq!(rgb(" gold "), Ok([255, 215, 0]));
+    }
+
+    #[test]
+    fn rejects_colours_it_cant_read() {
+        assert!(rgb("rebeccapurple").is_err());
+        assert!(rgb("#12").is_err());
+        assert!(rgb("#ggg").is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//! Headless AI versus AI games. Used by the `tournament` binary to measure how much going
+//! first matters and how the AI compute horizons compare. Boards come from the game's own
+//! generator so the results hold for real games. A seed can be given instead to deal the
+//! boards so that a tournament can be played again exactly.
+use std::fmt::Write;
+use std::num::NonZeroU8;
+
+use dicey_dice::{session, game};
+use dicey_dice::game::Board;
+
+use crate::dice::MAX_DICE;
+use crate::map::{self, Cell, MAX_PLAYERS};
+use crate::play;
+use crate::rng::Rng;
+
+/// How the tournament is played.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub columns: u32,
+    pub rows: u32,
+    pub seats: usize,
+    pub move_limit: NonZeroU8,
+
+    /// Each AI profile is a compute horizon. Profiles rotate through the seats from game
+    /// to game so that every profile gets a turn in every seat.
+    pub profiles: Vec<usize>,
+    pub games: usize,
+
+    /// Deal the boards from this seed rather than have the game generate them.
+    pub seed: Option<u64>,
+
+    /// Games still going after this many moves are called a stalemate.
+    pub max_moves: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            columns: 4,
+            rows: 4,
+            seats: 2,
+            move_limit: NonZeroU8::new(3).unwrap(),
+            profiles: vec![2],
+            games: 10,
+            seed: None,
+            max_moves: 500,
+        }
+    }
+}
+
+/// Random board for `seats` players dealt from the `rng`. The hexes are shuffled and dealt
+/// out in turn like cards so every seat gets its share, and at least one hex.
+pub fn seeded_board(columns: u32, rows: u32, seats: usize, rng: &mut Rng) -> Board {
+    let seats = seats.max(2).min(MAX_PLAYERS);
+    let mut order: Vec<usize> = (0..(columns * rows) as usize).collect();
+    for last in (1..order.len()).rev() {
+        order.swap(last, rng.range(0, last as u64) as usize);
+    }
+
+    let mut cells = vec![Cell::Blocked; order.len()];
+    order
+        .into_iter()
+        .enumerate()
+        .for_each(|(dealt, index)| {
+            cells[index] = Cell::Held {
+                owner: dealt % seats + 1,
+                dice: rng.range(1, MAX_DICE as u64) as u8,
+            };
+        });
+    map::build_board(columns, rows, &cells, map::seat_players(&vec![true; seats]), 0, 0)
+}
+
+/// The end of one game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Winner(usize),
+    Stalemate,
+}
+
+/// Play one game where the player in seat `n` uses the horizon at `horizons[n - 1]`.
+pub fn play_game(
+    board: Board, move_limit: NonZeroU8, horizons: &[usize], max_moves: usize,
+) -> Result<Outcome, String> {
+    let mut session = session::Setup::new()
+        .set_board(board)
+        .set_move_limit(move_limit)
+        .session()
+        .map_err(|e| format!("Failed to create game session: {}", &e))?;
+
+    for _ in 0..max_moves {
+        let (seat, outcome) = {
+            let state = session.current_turn();
+            let seat = *state.board().players().current().number();
+            let outcome = match state.game() {
+                session::Progression::PlayOn(_) => None,
+                session::Progression::GameOverWinner(player) => {
+                    Some(Outcome::Winner(*player.number()))
+                },
+                session::Progression::GameOverStalemate(_) => Some(Outcome::Stalemate),
+            };
+            (seat, outcome)
+        };
+
+        if let Some(outcome) = outcome {
+            return Ok(outcome);
+        }
+
+        let horizon = horizons[(seat - 1) % horizons.len()];
+        let index = {
+            let scored = session.score_with_depth_horizon(horizon);
+            play::best_choice(scored.choices().iter().enumerate())
+                .ok_or_else(|| "No choices to make".to_owned())?
+        };
+        session.advance(index).map_err(|e| e.to_string())?;
+    }
+
+    Ok(Outcome::Stalemate)
+}
+
+/// Games and wins for a seat, or a profile in a seat.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub games: usize,
+    pub wins: usize,
+}
+
+impl Tally {
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 {
+            0_f64
+        } else {
+            self.wins as f64 / self.games as f64
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub games: usize,
+    pub stalemates: usize,
+
+    /// Indexed by seat with the first seat first.
+    pub seats: Vec<Tally>,
+
+    /// Paired with the horizon of each profile, how it did in each seat. A profile that
+    /// sits in two seats in the same game is counted once in each.
+    pub profiles: Vec<(usize, Vec<Tally>)>,
+}
+
+impl Report {
+    fn new(seats: usize, profiles: &[usize]) -> Self {
+        Report {
+            games: 0,
+            stalemates: 0,
+            seats: vec![Tally::default(); seats],
+            profiles: profiles
+                .iter()
+                .map(|horizon| (*horizon, vec![Tally::default(); seats]))
+                .collect(),
+        }
+    }
+
+    /// Count a game where seat `n` was taken by the profile at `seating[n - 1]`.
+    fn record(&mut self, seating: &[usize], outcome: &Outcome) {
+        self.games += 1;
+        seating
+            .iter()
+            .enumerate()
+            .for_each(|(seat, profile)| {
+                self.seats[seat].games += 1;
+                self.profiles[*profile].1[seat].games += 1;
+            });
+
+        match outcome {
+            Outcome::Winner(player) if *player >= 1 && *player <= seating.len() => {
+                let seat = player - 1;
+                self.seats[seat].wins += 1;
+                self.profiles[seating[seat]].1[seat].wins += 1;
+            },
+            _ => self.stalemates += 1,
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("kind,horizon,seat,games,wins,win_rate\n");
+        self.seats
+            .iter()
+            .enumerate()
+            .for_each(|(seat, tally)| {
+                writeln!(
+                    csv, "seat,,{},{},{},{:.4}",
+                    seat + 1, tally.games, tally.wins, tally.win_rate(),
+                ).unwrap();
+            });
+        self.profiles
+            .iter()
+            .for_each(|(horizon, seats)| {
+                seats.iter().enumerate().for_each(|(seat, tally)| {
+                    writeln!(
+                        csv, "horizon,{},{},{},{},{:.4}",
+                        horizon, seat + 1, tally.games, tally.wins, tally.win_rate(),
+                    ).unwrap();
+                });
+            });
+        writeln!(csv, "stalemate,,,{},{},", self.games, self.stalemates).unwrap();
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        let entry = |seat: usize, tally: &Tally| {
+            format!(
+                "{{\"seat\":{},\"games\":{},\"wins\":{},\"win_rate\":{:.4}}}",
+                seat + 1, tally.games, tally.wins, tally.win_rate(),
+            )
+        };
+        let by_seat = |tallies: &[Tally]| {
+            tallies
+                .iter()
+                .enumerate()
+                .map(|(seat, tally)| entry(seat, tally))
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        let seats = by_seat(&self.seats);
+        let profiles: Vec<String> = self.profiles
+            .iter()
+            .map(|(horizon, seats)| {
+                format!("{{\"horizon\":{},\"seats\":[{}]}}", horizon, by_seat(seats))
+            })
+            .collect();
+
+        format!(
+            "{{\"games\":{},\"stalemates\":{},\"seats\":[{}],\"profiles\":[{}]}}\n",
+            self.games, self.stalemates, seats, profiles.join(","),
+        )
+    }
+}
+
+/// Play the whole tournament.
+pub fn run(settings: &Settings) -> Result<Report, String> {
+    let seats = settings.seats.max(2).min(MAX_PLAYERS);
+    let profiles = if settings.profiles.is_empty() {
+        vec![1]
+    } else {
+        settings.profiles.clone()
+    };
+
+    let mut rng = settings.seed.map(Rng::new);
+    let mut report = Report::new(seats, &profiles);
+
+    for game in 0..settings.games {
+        // Which profile sits in each seat this game.
+        let seating: Vec<usize> = (0..seats)
+            .map(|seat| (seat + game) % profiles.len())
+            .collect();
+        let horizons: Vec<usize> = seating
+            .iter()
+            .map(|profile| profiles[*profile])
+            .collect();
+
+        let board = match rng.as_mut() {
+            Some(rng) => seeded_board(settings.columns, settings.rows, seats, rng),
+            None => game::generate_random_board(
+                settings.columns, settings.rows, map::seat_players(&vec![true; seats]),
+            ),
+        };
+        let outcome = play_game(board, settings.move_limit, &horizons, settings.max_moves)?;
+        report.record(&seating, &outcome);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_profiles_are_tallied_by_seat() {
+        let mut report = Report::new(2, &[3]);
+        report.record(&[0, 0], &Outcome::Winner(2));
+        report.record(&[0, 0], &Outcome::Stalemate);
+
+        assert_eq!(report.games, 2);
+        assert_eq!(report.stalemates, 1);
+        assert_eq!(report.seats[1], Tally { games: 2, wins: 1 });
+        assert_eq!(report.profiles[0].1[0], Tally { games: 2, wins: 0 });
+        assert_eq!(report.profiles[0].1[1], Tally { games: 2, wins: 1 });
+    }
+
+    #[test]
+    fn seeded_boards_seat_everyone() {
+        let mut rng = Rng::new(7);
+        for seats in 2..=MAX_PLAYERS {
+            let board = seeded_board(2, 2, seats, &mut rng);
+            for seat in 1..=seats {
+                let owners: Vec<usize> = board
+                    .grid()
+                    .iter()
+                    .map(|hex| *hex.data().owner().number())
+                    .collect();
+                assert!(owners.contains(&seat));
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Guided tutorial. A scripted run of fixed boards where each step only lets the player
+//! click the hexes it's teaching about. Nothing about the dice is forced. Attacks are
+//! settled by the dice on each side rather than a roll so starting every step from its
+//! own board is enough for the outcome to be the one the script expects. The tests replay
+//! every step and check the exact board it leaves to keep it that way.
+use std::collections::HashSet;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::grid::Tessellation;
+use crate::notation::Snapshot;
+use crate::play::Game;
+use crate::primitive::{Orientation, Point};
+use crate::jslog;
+
+/// One step of the tutorial.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// Board to play the step on in the one line notation.
+    position: &'static str,
+
+    /// What to do.
+    instruction: &'static str,
+
+    /// Indexes of the only hexes that can be clicked. An attack between them finishes
+    /// the step.
+    allowed: &'static [usize],
+
+    /// Shown once the step is done.
+    outcome: &'static str,
+}
+
+pub const STEPS: &[Step] = &[
+    Step {
+        position: "A1A3A1/A1B2A1/B1B1B1_A_0_0_3",
+        instruction: "Each hex shows its dice. Click your hex with three dice and then the \
+                      enemy hex with two dice next to it to attack.",
+        allowed: &[1, 4],
+        outcome: "Attacks win when they have more dice than the defender. The winning dice \
+                  all move in except for one which stays behind.",
+    },
+    Step {
+        position: "A1A1A1/A1A2A1/B1B1B1_A_1_2_3",
+        instruction: "Attacks can carry on from a hex just taken. Attack the enemy hex \
+                      below with your two dice.",
+        allowed: &[4, 7],
+        outcome: "Each turn has a limit on how many moves can be made.",
+    },
+    Step {
+        position: "A1A1A1/A1A1A1/A4B2B1_A_0_0_3",
+        instruction: "A single dice can't attack and neither can a smaller stack. Use your \
+                      four dice to take the enemy's two.",
+        allowed: &[6, 7],
+        outcome: "That's the basics. Take every hex to win!",
+    },
+];
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Tutorial {
+    start: Point,
+    radius: u32,
+    orientation: Orientation,
+    step: usize,
+    game: Game,
+    pixel_ratio: f64,
+
+    /// What happened on the last step, if any.
+    outcome: &'static str,
+}
+
+fn step_game(
+    step: &Step, start: Point, radius: u32, orientation: Orientation,
+) -> Result<Game, String> {
+    let snapshot = Snapshot::parse(step.position).map_err(|e| e.to_string())?;
+    let template = snapshot.template(start, radius, orientation);
+    let session = snapshot.session()?;
+    Ok(Game::new(session, HashSet::new(), 1, template))
+}
+
+impl Tutorial {
+    pub (crate) fn new(
+        start: Point, radius: u32, orientation: Orientation,
+    ) -> Result<Self, String> {
+        let game = step_game(&STEPS[0], start, radius, orientation)?;
+        Ok(Tutorial {
+            start,
+            radius,
+            orientation,
+            step: 0,
+            game,
+            pixel_ratio: 1_f64,
+            outcome: "",
+        })
+    }
+
+    fn current(&self) -> Option<&Step> {
+        STEPS.get(self.step)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Tutorial {
+    pub fn tessellation(&self) -> Tessellation {
+        self.game.tessellation()
+    }
+
+    pub fn step_number(&self) -> usize {
+        self.step + 1
+    }
+
+    pub fn step_count(&self) -> usize {
+        STEPS.len()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.step >= STEPS.len()
+    }
+
+    /// What to do now. Empty once the tutorial is finished.
+    pub fn instruction(&self) -> String {
+        self.current()
+            .map(|step| step.instruction.to_owned())
+            .unwrap_or_default()
+    }
+
+    /// What came of the last step.
+    pub fn outcome(&self) -> String {
+        self.outcome.to_owned()
+    }
+
+    /// Indexes of the hexes the current step is about.
+    pub fn highlighted_hexes(&self) -> Vec<u32> {
+        self.current()
+            .map(|step| step.allowed.iter().map(|index| *index as u32).collect())
+            .unwrap_or_default()
+    }
+
+    /// Same as `Game::set_pixel_ratio`. Kept for every step.
+    pub fn set_pixel_ratio(&mut self, pixel_ratio: f64) {
+        self.pixel_ratio = if pixel_ratio > 0_f64 { pixel_ratio } else { 1_f64 };
+        self.game.set_pixel_ratio(self.pixel_ratio);
+    }
+
+    /// Same as `Game::select_hex_with_pixel` except that only the allowed hexes can be
+    /// clicked. Returns `true` when the step is done and the next one has begun.
+    pub fn select_hex_with_pixel(&mut self, pixel: Point) -> bool {
+        match self.game.index_at_pixel(pixel) {
+            Some(index) => self.select_hex_with_index(index),
+            None => {
+                jslog!("That hexagon isn't part of this step.");
+                false
+            },
+        }
+    }
+
+    /// Same as `select_hex_with_pixel` but picks the hex by its index.
+    pub fn select_hex_with_index(&mut self, index: usize) -> bool {
+        let step = match self.current() {
+            Some(step) => *step,
+            None => return false,
+        };
+
+        if !step.allowed.contains(&index) {
+            jslog!("That hexagon isn't part of this step.");
+            return false;
+        }
+
+        if !self.game.select_hex_with_index(index) {
+            return false;
+        }
+
+        self.outcome = step.outcome;
+        self.step += 1;
+        if let Some(next) = self.current() {
+            match step_game(next, self.start, self.radius, self.orientation) {
+                Ok(game) => {
+                    self.game = game;
+                    self.game.set_pixel_ratio(self.pixel_ratio);
+                },
+                Err(e) => {
+                    jslog!("Failed to start tutorial step: {}", &e);
+                    self.step = STEPS.len();
+                },
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The board each step leaves behind. The first step leads into the second.
+    const RESULTS: [&str; 3] = [
+        "A1A1A1/A1A2A1/B1B1B1_A_1_2_3",
+        "A1A1A1/A1A1A1/B1A1B1_A_2_3_3",
+        "A1A1A1/A1A1A1/A1A3B1_A_1_2_3",
+    ];
+
+    #[test]
+    fn every_attack_goes_the_way_the_script_says() {
+        assert_eq!(RESULTS.len(), STEPS.len());
+        assert_eq!(RESULTS[0], STEPS[1].position);
+        for (step, result) in STEPS.iter().zip(RESULTS.iter()) {
+            let mut game = step_game(step, Point::default(), 10, Orientation::Pointy)
+                .unwrap();
+            let (from, to) = (step.allowed[0], step.allowed[1]);
+            assert!(!game.select_hex_with_index(from), "{}", step.position);
+            assert!(game.select_hex_with_index(to), "{}", step.position);
+            assert_eq!(&game.position(), result, "{}", step.position);
+        }
+    }
+
+    #[test]
+    fn steps_through_to_the_end() {
+        let mut tutorial = Tutorial::new(Point::default(), 10, Orientation::Flat).unwrap();
+        for (number, step) in STEPS.iter().enumerate() {
+            assert_eq!(tutorial.step_number(), number + 1);
+            assert!(!tutorial.select_hex_with_index(step.allowed[0]));
+            assert!(tutorial.select_hex_with_index(step.allowed[1]));
+            assert_eq!(tutorial.outcome(), step.outcome);
+        }
+        assert!(tutorial.finished());
+    }
+
+    #[test]
+    fn takes_ratios_that_arent_positive_as_one() {
+        let mut tutorial = Tutorial::new(Point::new(10, 10), 10, Orientation::Pointy)
+            .unwrap();
+        let center = tutorial.tessellation().hex(4).center();
+        for ratio in &[0_f64, -2_f64, std::f64::NAN] {
+            tutorial.set_pixel_ratio(*ratio);
+            assert_eq!(tutorial.game.index_at_pixel(center), Some(4), "{}", ratio);
+        }
+    }
+
+    #[test]
+    fn only_allowed_hexes_can_be_picked() {
+        let mut tutorial = Tutorial::new(Point::default(), 10, Orientation::Flat).unwrap();
+        let other = (0..9).find(|index| !STEPS[0].allowed.contains(index)).unwrap();
+        assert!(!tutorial.select_hex_with_index(other));
+        assert_eq!(tutorial.step_number(), 1);
+    }
+}
@@ -9,3 +9,53 @@ cfg_if! {
         pub fn set_panic_hook() { }
     }
 }
+
+cfg_if! {
+    if #[cfg(feature = "wasm")] {
+        use wasm_bindgen::prelude::*;
+
+        /// Errors handed back to JS.
+        pub type Failure = JsValue;
+
+        pub fn failure(message: &str) -> Failure {
+            JsValue::from_str(message)
+        }
+
+        #[wasm_bindgen]
+        extern {
+            #[wasm_bindgen(js_namespace = console, js_name = log)]
+            fn console_log(msg: &str);
+        }
+
+        /// Sends everything logged to the JS console.
+        struct ConsoleLogger;
+
+        impl log::Log for ConsoleLogger {
+            fn enabled(&self, _metadata: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                console_log(&record.args().to_string());
+            }
+
+            fn flush(&self) { }
+        }
+
+        static LOGGER: ConsoleLogger = ConsoleLogger;
+
+        /// Only the first call does anything.
+        pub fn set_console_logger() {
+            if log::set_logger(&LOGGER).is_ok() {
+                log::set_max_level(log::LevelFilter::Info);
+            }
+        }
+    } else {
+        /// Errors handed back to native callers.
+        pub type Failure = String;
+
+        pub fn failure(message: &str) -> Failure {
+            message.to_owned()
+        }
+    }
+}
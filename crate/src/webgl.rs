@@ -0,0 +1,641 @@
+//! WebGL2 drawing for big boards and smooth animation. The hex geometry is uploaded once
+//! as static buffers. Every hex has its own stretch of the colour and dice buffers so only
+//! the hexes that changed are sent again. Dice and their pips are drawn as instanced quads
+//! laid out by `render`.
+use js_sys::Float32Array;
+use wasm_bindgen::prelude::*;
+use web_sys::{
+    WebGl2RenderingContext as Gl, WebGlBuffer, WebGlProgram, WebGlShader,
+    WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+use crate::camera::Camera;
+use crate::dice::MAX_DICE;
+use crate::grid::Tessellation;
+use crate::hex::Detail;
+use crate::play::Game;
+use crate::primitive::{Orientation, Point};
+use crate::render::{self, Command};
+use crate::theme::{self, Theme};
+use crate::utils::{failure, Failure};
+use crate::jslog;
+
+const SHAPE_VERTEX: &str = r#"#version 300 es
+in vec2 a_position;
+in vec4 a_colour;
+uniform vec2 u_resolution;
+uniform vec3 u_camera;
+out vec4 v_colour;
+
+void main() {
+    vec2 position = a_position * u_camera.z + u_camera.xy;
+    vec2 clip = position / u_resolution * 2.0 - 1.0;
+    gl_Position = vec4(clip * vec2(1.0, -1.0), 0.0, 1.0);
+    v_colour = a_colour;
+}
+"#;
+
+const SHAPE_FRAGMENT: &str = r#"#version 300 es
+precision mediump float;
+in vec4 v_colour;
+out vec4 colour;
+
+void main() {
+    colour = v_colour;
+}
+"#;
+
+const QUAD_VERTEX: &str = r#"#version 300 es
+in vec2 a_corner;
+in vec2 i_origin;
+in float i_size;
+in vec4 i_colour;
+in float i_round;
+uniform vec2 u_resolution;
+uniform vec3 u_camera;
+out vec2 v_corner;
+out vec4 v_colour;
+out float v_round;
+
+void main() {
+    vec2 position = (i_origin + a_corner * i_size) * u_camera.z + u_camera.xy;
+    vec2 clip = position / u_resolution * 2.0 - 1.0;
+    gl_Position = vec4(clip * vec2(1.0, -1.0), 0.0, 1.0);
+    v_corner = a_corner;
+    v_colour = i_colour;
+    v_round = i_round;
+}
+"#;
+
+const QUAD_FRAGMENT: &str = r#"#version 300 es
+precision mediump float;
+in vec2 v_corner;
+in vec4 v_colour;
+in float v_round;
+out vec4 colour;
+
+void main() {
+    if (v_round > 0.5 && distance(v_corner, vec2(0.5)) > 0.5) {
+        discard;
+    }
+    colour = v_colour;
+}
+"#;
+
+/// Each hex is filled as six triangles around its center.
+const FILL_VERTICES: usize = 18;
+
+/// Each hex is outlined as six lines.
+const EDGE_VERTICES: usize = 12;
+
+/// Floats per die or pip instance. The origin, size, colour and whether it's round.
+const INSTANCE_FLOATS: usize = 8;
+
+/// Die and pip instances set aside for each hex. A face and an outline for each die and
+/// as many pips as its place in the stack. Unused ones are left empty and draw nothing.
+const DICE_INSTANCES: usize = MAX_DICE as usize * 2
+    + MAX_DICE as usize * (MAX_DICE as usize + 1) / 2;
+
+/// Unit quad as two triangles, scaled and moved into place for each instance.
+const UNIT_QUAD: [f32; 12] = [0., 0., 1., 0., 0., 1., 0., 1., 1., 0., 1., 1.];
+
+fn compile(gl: &Gl, kind: u32, source: &str) -> Result<WebGlShader, String> {
+    let shader = gl
+        .create_shader(kind)
+        .ok_or_else(|| "Unable to create shader".to_owned())?;
+    gl.shader_source(&shader, source);
+    gl.compile_shader(&shader);
+
+    let compiled = gl
+        .get_shader_parameter(&shader, Gl::COMPILE_STATUS)
+        .as_bool()
+        .unwrap_or(false);
+    if compiled {
+        Ok(shader)
+    } else {
+        Err(gl.get_shader_info_log(&shader).unwrap_or_default())
+    }
+}
+
+fn link(gl: &Gl, vertex: &str, fragment: &str) -> Result<WebGlProgram, String> {
+    let vertex = compile(gl, Gl::VERTEX_SHADER, vertex)?;
+    let fragment = compile(gl, Gl::FRAGMENT_SHADER, fragment)?;
+    let program = gl
+        .create_program()
+        .ok_or_else(|| "Unable to create shader program".to_owned())?;
+    gl.attach_shader(&program, &vertex);
+    gl.attach_shader(&program, &fragment);
+    gl.link_program(&program);
+
+    let linked = gl
+        .get_program_parameter(&program, Gl::LINK_STATUS)
+        .as_bool()
+        .unwrap_or(false);
+    if linked {
+        Ok(program)
+    } else {
+        Err(gl.get_program_info_log(&program).unwrap_or_default())
+    }
+}
+
+fn create_buffer(gl: &Gl) -> Result<WebGlBuffer, String> {
+    gl.create_buffer().ok_or_else(|| "Unable to create buffer".to_owned())
+}
+
+fn upload(gl: &Gl, buffer: &WebGlBuffer, data: &[f32], usage: u32) {
+    gl.bind_buffer(Gl::ARRAY_BUFFER, Some(buffer));
+    // The view is only valid until wasm memory next grows. Nothing is allocated before
+    // it's handed over.
+    unsafe {
+        let view = Float32Array::view(data);
+        gl.buffer_data_with_array_buffer_view(Gl::ARRAY_BUFFER, &view, usage);
+    }
+}
+
+/// Overwrite part of a buffer already uploaded, from `offset` floats in.
+fn upload_range(gl: &Gl, buffer: &WebGlBuffer, offset: usize, data: &[f32]) {
+    gl.bind_buffer(Gl::ARRAY_BUFFER, Some(buffer));
+    // As with `upload` the view is handed straight over.
+    unsafe {
+        let view = Float32Array::view(data);
+        gl.buffer_sub_data_with_i32_and_array_buffer_view(
+            Gl::ARRAY_BUFFER, (offset * 4) as i32, &view,
+        );
+    }
+}
+
+/// Point the float attribute `name` at the currently bound buffer. The `stride` and
+/// `offset` are counted in floats.
+fn attribute(
+    gl: &Gl,
+    program: &WebGlProgram,
+    name: &str,
+    size: i32,
+    stride: usize,
+    offset: usize,
+    divisor: u32,
+) -> Result<(), String> {
+    let location = gl.get_attrib_location(program, name);
+    if location < 0 {
+        return Err(format!("No attribute named {}", name));
+    }
+    let location = location as u32;
+    gl.enable_vertex_attrib_array(location);
+    gl.vertex_attrib_pointer_with_i32(
+        location, size, Gl::FLOAT, false, (stride * 4) as i32, (offset * 4) as i32,
+    );
+    gl.vertex_attrib_divisor(location, divisor);
+    Ok(())
+}
+
+/// Colours are checked when the theme is set so this should never fall back to black.
+fn rgba(css: &str) -> [f32; 4] {
+    let [r, g, b] = theme::rgb(css).unwrap_or_else(|e| {
+        jslog!("{}", e);
+        [0, 0, 0]
+    });
+    [r as f32 / 255., g as f32 / 255., b as f32 / 255., 1.]
+}
+
+/// Every colour of the `theme` can be drawn.
+fn check_theme(theme: &Theme) -> Result<(), String> {
+    [theme.die(), theme.dot(), theme.outline(), theme.blocked(), theme.highlight()]
+        .iter()
+        .map(|css| theme::rgb(css).map(|_| ()))
+        .collect()
+}
+
+fn corners(detail: &Detail) -> Vec<Point> {
+    (0..6).map(|corner| detail.point(corner)).collect()
+}
+
+/// Triangles and lines coloured vertex by vertex.
+struct Shapes {
+    vao: WebGlVertexArrayObject,
+    positions: WebGlBuffer,
+    colours: WebGlBuffer,
+    count: i32,
+}
+
+impl Shapes {
+    fn new(gl: &Gl, program: &WebGlProgram) -> Result<Self, String> {
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create vertex array".to_owned())?;
+        let positions = create_buffer(gl)?;
+        let colours = create_buffer(gl)?;
+
+        gl.bind_vertex_array(Some(&vao));
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&positions));
+        attribute(gl, program, "a_position", 2, 2, 0, 0)?;
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&colours));
+        attribute(gl, program, "a_colour", 4, 4, 0, 0)?;
+        gl.bind_vertex_array(None);
+
+        Ok(Shapes { vao, positions, colours, count: 0 })
+    }
+
+    fn set_positions(&mut self, gl: &Gl, positions: &[f32], usage: u32) {
+        upload(gl, &self.positions, positions, usage);
+        self.count = (positions.len() / 2) as i32;
+    }
+
+    fn set_colours(&self, gl: &Gl, colours: &[f32]) {
+        upload(gl, &self.colours, colours, Gl::DYNAMIC_DRAW);
+    }
+
+    /// Colour the vertices from `vertex` on.
+    fn update_colours(&self, gl: &Gl, vertex: usize, colours: &[f32]) {
+        upload_range(gl, &self.colours, vertex * 4, colours);
+    }
+
+    fn draw(&self, gl: &Gl, mode: u32) {
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.draw_arrays(mode, 0, self.count);
+        gl.bind_vertex_array(None);
+    }
+}
+
+/// Squares and circles drawn as instances of the unit quad.
+struct Quads {
+    vao: WebGlVertexArrayObject,
+    instances: WebGlBuffer,
+    count: i32,
+}
+
+impl Quads {
+    fn new(gl: &Gl, program: &WebGlProgram) -> Result<Self, String> {
+        let vao = gl
+            .create_vertex_array()
+            .ok_or_else(|| "Unable to create vertex array".to_owned())?;
+        let corners = create_buffer(gl)?;
+        let instances = create_buffer(gl)?;
+
+        gl.bind_vertex_array(Some(&vao));
+        upload(gl, &corners, &UNIT_QUAD, Gl::STATIC_DRAW);
+        attribute(gl, program, "a_corner", 2, 2, 0, 0)?;
+        gl.bind_buffer(Gl::ARRAY_BUFFER, Some(&instances));
+        attribute(gl, program, "i_origin", 2, INSTANCE_FLOATS, 0, 1)?;
+        attribute(gl, program, "i_size", 1, INSTANCE_FLOATS, 2, 1)?;
+        attribute(gl, program, "i_colour", 4, INSTANCE_FLOATS, 3, 1)?;
+        attribute(gl, program, "i_round", 1, INSTANCE_FLOATS, 7, 1)?;
+        gl.bind_vertex_array(None);
+
+        Ok(Quads { vao, instances, count: 0 })
+    }
+
+    fn set_instances(&mut self, gl: &Gl, instances: &[f32]) {
+        upload(gl, &self.instances, instances, Gl::DYNAMIC_DRAW);
+        self.count = (instances.len() / INSTANCE_FLOATS) as i32;
+    }
+
+    /// Replace the instances from `instance` on.
+    fn update_instances(&self, gl: &Gl, instance: usize, instances: &[f32]) {
+        upload_range(gl, &self.instances, instance * INSTANCE_FLOATS, instances);
+    }
+
+    fn draw(&self, gl: &Gl) {
+        gl.bind_vertex_array(Some(&self.vao));
+        gl.draw_arrays_instanced(Gl::TRIANGLES, 0, 6, self.count);
+        gl.bind_vertex_array(None);
+    }
+}
+
+/// What the static geometry was built from. It's built again if the board changes shape.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Layout {
+    columns: u32,
+    rows: u32,
+    radius: u32,
+    orientation: Orientation,
+    start: Point,
+}
+
+impl Layout {
+    fn of(tessellation: &Tessellation) -> Self {
+        Layout {
+            columns: tessellation.columns(),
+            rows: tessellation.rows(),
+            radius: tessellation.radius(),
+            orientation: tessellation.orientation(),
+            start: tessellation.start_hex_center(),
+        }
+    }
+}
+
+fn push_point(buffer: &mut Vec<f32>, point: Point) {
+    buffer.extend_from_slice(&[point.x() as f32, point.y() as f32]);
+}
+
+fn fill_positions(tessellation: &Tessellation) -> Vec<f32> {
+    let mut positions = Vec::with_capacity(tessellation.len() * FILL_VERTICES * 2);
+    (0..tessellation.len())
+        .map(|index| tessellation.hex(index))
+        .for_each(|detail| {
+            let points = corners(&detail);
+            (0..6).for_each(|corner| {
+                push_point(&mut positions, detail.center());
+                push_point(&mut positions, points[corner]);
+                push_point(&mut positions, points[(corner + 1) % 6]);
+            });
+        });
+    positions
+}
+
+fn edge_positions(tessellation: &Tessellation) -> Vec<f32> {
+    let mut positions = Vec::with_capacity(tessellation.len() * EDGE_VERTICES * 2);
+    (0..tessellation.len())
+        .map(|index| tessellation.hex(index))
+        .for_each(|detail| {
+            let points = corners(&detail);
+            (0..6).for_each(|corner| {
+                push_point(&mut positions, points[corner]);
+                push_point(&mut positions, points[(corner + 1) % 6]);
+            });
+        });
+    positions
+}
+
+/// Edges `width` wide as pairs of triangles. WebGL doesn't promise lines wider than one.
+fn thick_edges(points: &[Point], width: f32) -> Vec<f32> {
+    let mut positions = Vec::with_capacity(points.len() * 12);
+    (0..points.len()).for_each(|corner| {
+        let from = points[corner];
+        let to = points[(corner + 1) % points.len()];
+        let (fx, fy) = (from.x() as f32, from.y() as f32);
+        let (tx, ty) = (to.x() as f32, to.y() as f32);
+        let length = ((tx - fx).powi(2) + (ty - fy).powi(2)).sqrt().max(1.);
+        let nx = -(ty - fy) / length * width / 2.;
+        let ny = (tx - fx) / length * width / 2.;
+        positions.extend_from_slice(&[
+            fx + nx, fy + ny, fx - nx, fy - ny, tx + nx, ty + ny,
+            tx + nx, ty + ny, fx - nx, fy - ny, tx - nx, ty - ny,
+        ]);
+    });
+    positions
+}
+
+fn push_instance(
+    buffer: &mut Vec<f32>, origin: (f32, f32), size: f32, colour: &str, round: bool,
+) {
+    let [r, g, b, a] = rgba(colour);
+    let round = if round { 1. } else { 0. };
+    buffer.extend_from_slice(&[origin.0, origin.1, size, r, g, b, a, round]);
+}
+
+/// Dice faces, their outlines and pips of a hex from the same layout as the other
+/// backends. Always `DICE_INSTANCES` long, padded out with empty instances.
+fn dice_instances(detail: &Detail, radius: u32, theme: &Theme) -> Vec<f32> {
+    let mut instances = Vec::with_capacity(DICE_INSTANCES * INSTANCE_FLOATS);
+    render::hex(detail, radius, theme)
+        .into_iter()
+        .for_each(|command| match command {
+            Command::Rect { top_left, width, paint, .. } => {
+                let (x, y) = (top_left.x() as f32, top_left.y() as f32);
+                if let Some(stroke) = paint.stroke.as_ref() {
+                    let origin = (x - 1., y - 1.);
+                    let size = width as f32 + 2.;
+                    push_instance(&mut instances, origin, size, stroke, false);
+                }
+                if let Some(fill) = paint.fill.as_ref() {
+                    push_instance(&mut instances, (x, y), width as f32, fill, false);
+                }
+            },
+            Command::Circle { center, radius, paint } => {
+                if let Some(fill) = paint.fill.as_ref() {
+                    let origin = (
+                        (center.x() - radius as i32) as f32,
+                        (center.y() - radius as i32) as f32,
+                    );
+                    let size = radius as f32 * 2.;
+                    push_instance(&mut instances, origin, size, fill, true);
+                }
+            },
+            _ => (),
+        });
+    instances.resize(DICE_INSTANCES * INSTANCE_FLOATS, 0.);
+    instances
+}
+
+/// `colour` for each of `count` vertices.
+fn repeat(colour: [f32; 4], count: usize) -> Vec<f32> {
+    (0..count).flat_map(|_| colour.iter().cloned()).collect()
+}
+
+#[wasm_bindgen]
+pub struct WebGlRenderer {
+    gl: Gl,
+    theme: Theme,
+    shape_program: WebGlProgram,
+    quad_program: WebGlProgram,
+    shape_resolution: Option<WebGlUniformLocation>,
+    quad_resolution: Option<WebGlUniformLocation>,
+    shape_camera: Option<WebGlUniformLocation>,
+    quad_camera: Option<WebGlUniformLocation>,
+    camera: Camera,
+    fills: Shapes,
+    edges: Shapes,
+    highlights: Shapes,
+    dice: Quads,
+    layout: Option<Layout>,
+
+    /// Each hex as it was last uploaded, to tell which have changed since.
+    drawn: Vec<Detail>,
+}
+
+impl WebGlRenderer {
+    fn build(gl: Gl, theme: Theme) -> Result<Self, String> {
+        check_theme(&theme)?;
+        let shape_program = link(&gl, SHAPE_VERTEX, SHAPE_FRAGMENT)?;
+        let quad_program = link(&gl, QUAD_VERTEX, QUAD_FRAGMENT)?;
+        let shape_resolution = gl.get_uniform_location(&shape_program, "u_resolution");
+        let quad_resolution = gl.get_uniform_location(&quad_program, "u_resolution");
+        let shape_camera = gl.get_uniform_location(&shape_program, "u_camera");
+        let quad_camera = gl.get_uniform_location(&quad_program, "u_camera");
+        let fills = Shapes::new(&gl, &shape_program)?;
+        let edges = Shapes::new(&gl, &shape_program)?;
+        let highlights = Shapes::new(&gl, &shape_program)?;
+        let dice = Quads::new(&gl, &quad_program)?;
+
+        // Blocked hexes are filled see through.
+        gl.enable(Gl::BLEND);
+        gl.blend_func(Gl::SRC_ALPHA, Gl::ONE_MINUS_SRC_ALPHA);
+
+        Ok(WebGlRenderer {
+            gl,
+            theme,
+            shape_program,
+            quad_program,
+            shape_resolution,
+            quad_resolution,
+            shape_camera,
+            quad_camera,
+            camera: Camera::new(),
+            fills,
+            edges,
+            highlights,
+            dice,
+            layout: None,
+            drawn: Vec::new(),
+        })
+    }
+
+    /// Fill and outline colour of a hex.
+    fn hex_colours(&self, detail: &Detail) -> ([f32; 4], [f32; 4]) {
+        if detail.absent() {
+            ([0., 0., 0., 0.], [0., 0., 0., 0.])
+        } else if detail.blocked() && !detail.water() {
+            ([0., 0., 0., 0.], rgba(self.theme.blocked()))
+        } else {
+            let [r, g, b] = detail.colour().rgb();
+            let fill = [r as f32 / 255., g as f32 / 255., b as f32 / 255., 1.];
+            (fill, rgba(self.theme.outline()))
+        }
+    }
+
+    /// Upload the geometry, colours and dice of every hex if the board has changed shape
+    /// since last time. Whether it had to.
+    fn prepare_layout(&mut self, tessellation: &Tessellation) -> bool {
+        let layout = Layout::of(tessellation);
+        if self.layout == Some(layout) {
+            return false;
+        }
+        self.fills.set_positions(&self.gl, &fill_positions(tessellation), Gl::STATIC_DRAW);
+        self.edges.set_positions(&self.gl, &edge_positions(tessellation), Gl::STATIC_DRAW);
+
+        let mut fills = Vec::with_capacity(tessellation.len() * FILL_VERTICES * 4);
+        let mut edges = Vec::with_capacity(tessellation.len() * EDGE_VERTICES * 4);
+        let mut instances = Vec::with_capacity(
+            tessellation.len() * DICE_INSTANCES * INSTANCE_FLOATS,
+        );
+        self.drawn = (0..tessellation.len()).map(|index| tessellation.hex(index)).collect();
+        self.drawn.iter().for_each(|detail| {
+            let (fill, edge) = self.hex_colours(detail);
+            fills.extend(repeat(fill, FILL_VERTICES));
+            edges.extend(repeat(edge, EDGE_VERTICES));
+            instances.extend(dice_instances(detail, tessellation.radius(), &self.theme));
+        });
+        self.fills.set_colours(&self.gl, &fills);
+        self.edges.set_colours(&self.gl, &edges);
+        self.dice.set_instances(&self.gl, &instances);
+        self.layout = Some(layout);
+        true
+    }
+
+    /// Send the colours and dice of the hex at `index` again.
+    fn update_hex(&mut self, tessellation: &Tessellation, index: usize) {
+        let detail = tessellation.hex(index);
+        let (fill, edge) = self.hex_colours(&detail);
+        let (fills, edges) = (repeat(fill, FILL_VERTICES), repeat(edge, EDGE_VERTICES));
+        self.fills.update_colours(&self.gl, index * FILL_VERTICES, &fills);
+        self.edges.update_colours(&self.gl, index * EDGE_VERTICES, &edges);
+        let instances = dice_instances(&detail, tessellation.radius(), &self.theme);
+        self.dice.update_instances(&self.gl, index * DICE_INSTANCES, &instances);
+        self.drawn[index] = detail;
+    }
+
+    /// Draw everything that's been uploaded.
+    fn present(&self) {
+        self.set_uniforms();
+        self.gl.use_program(Some(&self.shape_program));
+        self.fills.draw(&self.gl, Gl::TRIANGLES);
+        self.edges.draw(&self.gl, Gl::LINES);
+        self.gl.use_program(Some(&self.quad_program));
+        self.dice.draw(&self.gl);
+    }
+
+    /// Hand the canvas size and camera over to both programs.
+    fn set_uniforms(&self) {
+        let width = self.gl.drawing_buffer_width();
+        let height = self.gl.drawing_buffer_height();
+        self.gl.viewport(0, 0, width, height);
+        let (x, y, scale) = self.camera.backing_transform();
+        let (x, y, scale) = (x as f32, y as f32, scale as f32);
+
+        self.gl.use_program(Some(&self.quad_program));
+        self.gl.uniform2f(self.quad_resolution.as_ref(), width as f32, height as f32);
+        self.gl.uniform3f(self.quad_camera.as_ref(), x, y, scale);
+        self.gl.use_program(Some(&self.shape_program));
+        self.gl.uniform2f(self.shape_resolution.as_ref(), width as f32, height as f32);
+        self.gl.uniform3f(self.shape_camera.as_ref(), x, y, scale);
+    }
+}
+
+#[wasm_bindgen]
+impl WebGlRenderer {
+    #[wasm_bindgen(constructor)]
+    pub fn new(context: Gl, theme: Theme) -> Result<WebGlRenderer, Failure> {
+        WebGlRenderer::build(context, theme).map_err(|e| failure(&e))
+    }
+
+    /// Every hex is uploaded again in the new colours the next time the board is drawn.
+    /// Themes with colours that WebGL can't be given are turned down.
+    pub fn set_theme(&mut self, theme: Theme) -> Result<(), Failure> {
+        check_theme(&theme).map_err(|e| failure(&e))?;
+        self.theme = theme;
+        self.layout = None;
+        Ok(())
+    }
+
+    /// Draw through a copy of `camera`. Set it again after panning, zooming or a change of
+    /// pixel ratio. Highlights are made thicker on high DPI screens to match.
+    pub fn set_camera(&mut self, camera: &Camera) {
+        self.camera = *camera;
+    }
+
+    pub fn clear(&self) {
+        self.gl.clear_color(0., 0., 0., 0.);
+        self.gl.clear(Gl::COLOR_BUFFER_BIT);
+    }
+
+    /// Draw every hex of the `tessellation` along with its dice. Only the hexes that look
+    /// different from the last time are uploaded again.
+    pub fn draw(&mut self, tessellation: &Tessellation) {
+        if !self.prepare_layout(tessellation) {
+            let changed: Vec<usize> = (0..tessellation.len())
+                .filter(|index| self.drawn[*index] != tessellation.hex(*index))
+                .collect();
+            changed
+                .into_iter()
+                .for_each(|index| self.update_hex(tessellation, index));
+        }
+        self.present();
+    }
+
+    /// Upload the hexes at `indexes` again then draw the board. Pair with
+    /// `Game::dirty_hexes` so only what changed is sent.
+    pub fn draw_hexes(&mut self, tessellation: &Tessellation, indexes: &[u32]) {
+        if !self.prepare_layout(tessellation) {
+            indexes
+                .iter()
+                .map(|index| *index as usize)
+                .filter(|index| *index < tessellation.len())
+                .for_each(|index| self.update_hex(tessellation, index));
+        }
+        self.present();
+    }
+
+    /// Draw the hexes of `game` that changed since last time and mark them drawn.
+    pub fn draw_dirty(&mut self, game: &mut Game) {
+        let dirty = game.dirty_hexes();
+        self.draw_hexes(game.current_tessellation(), &dirty);
+        game.clear_dirty();
+    }
+
+    /// Outline the hexes at `indexes` in the highlight colour.
+    pub fn draw_highlights(&mut self, tessellation: &Tessellation, indexes: &[u32]) {
+        let width = 4. * self.camera.pixel_ratio() as f32;
+        let positions: Vec<f32> = indexes
+            .iter()
+            .map(|index| tessellation.hex(*index as usize))
+            .flat_map(|detail| thick_edges(&corners(&detail), width))
+            .collect();
+        let colours = repeat(rgba(self.theme.highlight()), positions.len() / 2);
+
+        self.highlights.set_positions(&self.gl, &positions, Gl::DYNAMIC_DRAW);
+        self.highlights.set_colours(&self.gl, &colours);
+        self.set_uniforms();
+        self.gl.use_program(Some(&self.shape_program));
+        self.highlights.draw(&self.gl, Gl::TRIANGLES);
+    }
+}
@@ -1,6 +1,5 @@
 import * as dicey from "../crate/pkg/wasm_dicey";
 import { memory } from "../crate/pkg/wasm_dicey_bg";
-import * as board from "./board.js";
 import * as prepare from "./prepare.js";
 //import * as advance from "./advance.js";
 
@@ -28,17 +27,74 @@ const drawHexBoardRelativeCircle = (context, point, radius) => {
     context.fill();
 }
 
-// Game board top left hex center point
-const tl_point = dicey.Point.new(HEX_RADIUS, HEX_RADIUS);
+// A new game board top left hex center point. Points are used up when handed to the game
+// so each board needs its own.
+const top_left = () => dicey.Point.new(HEX_RADIUS, HEX_RADIUS);
 
-// Setup our canvas
+// Setup our canvas. It's square and as wide as the page allows, up to `SIDE` CSS pixels.
+// The backing store has a pixel for every device pixel so boards are crisp on high DPI
+// screens.
 const canvas = document.getElementById("dice-board");
-canvas.height = SIDE;
-canvas.width = SIDE;
-const ctx = canvas.getContext('2d');
+var canvas_side = SIDE;
+var pixel_ratio = 1;
+const size_canvas = () => {
+    canvas_side = Math.min(SIDE, canvas.parentNode.clientWidth);
+    pixel_ratio = window.devicePixelRatio || 1;
+    canvas.style.width = canvas_side + "px";
+    canvas.style.height = canvas_side + "px";
+    canvas.width = Math.round(canvas_side * pixel_ratio);
+    canvas.height = Math.round(canvas_side * pixel_ratio);
+}
+size_canvas();
+
+// Add `?webgl` to the address to draw with WebGL2 instead of the 2D canvas.
+const theme = dicey.Theme.new(DIE_COLOUR, DOT_COLOUR);
+const renderer = new URLSearchParams(window.location.search).has("webgl")
+      ? new dicey.WebGlRenderer(canvas.getContext('webgl2'), theme)
+      : new dicey.CanvasRenderer(canvas.getContext('2d'), theme);
+
+// Space left around the board. The same as `Game::relayout` leaves.
+const BOARD_MARGIN = 8;
+
+// Which part of the board is on the canvas. Drag to pan and scroll to zoom.
+const camera = dicey.Camera.new();
+
+// Zoom and pan so the whole of a newly shown game, editor or tutorial fits the canvas.
+// The board is told the pixel ratio so that clicks in CSS pixels find the right hex.
+const fit_camera = board => {
+    board.set_pixel_ratio(pixel_ratio);
+    camera.set_pixel_ratio(pixel_ratio);
+    camera.fit(board.tessellation(), canvas_side, canvas_side, BOARD_MARGIN);
+    renderer.set_camera(camera);
+}
+
+// The hex orientation chosen in the settings.
+const chosen_orientation = () => document.getElementById("orientation").value == "flat"
+    ? dicey.Orientation.Flat
+    : dicey.Orientation.Pointy;
+
+// Setup our game. A position in the URL fragment is played if there is one, otherwise
+// this is just an example one to start off.
+const start_game = () => {
+    let position = decodeURIComponent(window.location.hash.slice(1));
+    if (position.length > 0) {
+        try {
+            return dicey.start_game_from_position(
+                position, top_left(), HEX_RADIUS, chosen_orientation(), 1, 1, 1, 1, 1
+            );
+        } catch (e) {
+            console.error("Can't play shared position: ", e);
+        }
+    }
+    return dicey.game_3x3_init(top_left(), HEX_RADIUS, chosen_orientation());
+}
+var game = start_game();
+
+// Set while editing a board. Clicks go to the editor instead of the game.
+var editor = null;
 
-// Setup our game. This is just an example one to start off.
-var game = dicey.game_3x3_init(tl_point, HEX_RADIUS);
+// Set while running the tutorial. Clicks go to the tutorial instead of the game.
+var tutorial = null;
 
 const display_player = (num, moves, captured, ai) => {
     if (ai) {
@@ -69,6 +125,52 @@ const add_battle_log_items = (items) => {
     }
 }
 
+// Where the player, dice and flags of a hex are within its `dicey.packed_stride()`
+// numbers of the packed buffer. See `PACKED_STRIDE` in hex.rs.
+const PACKED_STRIDE = dicey.packed_stride();
+const PACKED_PLAYER = 15;
+const PACKED_DICE = 16;
+
+// View the hexes of a tessellation, or the game holding one, straight out of wasm memory.
+// The view is only good until wasm next allocates so it must be used straight away.
+const packed_hexes = board => {
+    board.pack();
+    return new Int32Array(memory.buffer, board.packed_ptr(), board.packed_len());
+}
+
+// Show how many hexes and dice each player holds.
+const display_territory = board => {
+    let hexes = packed_hexes(board);
+    let held = new Map();
+    for (var i = 0; i < hexes.length; i += PACKED_STRIDE) {
+        let player = hexes[i + PACKED_PLAYER];
+        if (player == 0) {
+            continue;
+        }
+        let [count, dice] = held.get(player) || [0, 0];
+        held.set(player, [count + 1, dice + hexes[i + PACKED_DICE]]);
+    }
+    let text = [...held.keys()].sort((a, b) => a - b).map(player => {
+        let [count, dice] = held.get(player);
+        return "Player" + player + ": " + count + " hexes, " + dice + " dice";
+    });
+    document.getElementById("territory").innerText = text.join(". ");
+}
+
+// Draw the whole game from scratch.
+const draw_game = () => {
+    renderer.clear();
+    renderer.draw(game.tessellation());
+    display_territory(game);
+}
+
+// Redraw only the hexes that changed since last time. The game is handed over as it is
+// rather than copying its tessellation out.
+const draw_dirty = () => {
+    renderer.draw_dirty(game);
+    display_territory(game);
+}
+
 const play_on = () => {
     while (game.advance()) {
         let log_items = game.state_log();
@@ -77,7 +179,7 @@ const play_on = () => {
         let captured_dice = game.current_player_dice_captured();
         let is_ai = game.current_player_ai();
         display_player(player_id, player_moves_left, captured_dice, is_ai);
-        board.drawGameBoard(ctx, DIE_COLOUR, DOT_COLOUR, game.tessellation());
+        draw_dirty();
         add_battle_log_items(log_items);
         setTimeout(function() { 1 + 1; }, 1000);
     }
@@ -93,25 +195,36 @@ playButton.addEventListener("click", event => {
     var player4 = document.getElementById("player4").value;
     var turns   = document.getElementById("turns").value;
     var horizon = document.getElementById("horizon").value;
+    var balanced = document.getElementById("balanced").checked;
+    var tolerance = document.getElementById("tolerance").value;
+    var orientation = chosen_orientation();
+    var shape = prepare.shape_option_to_code(document.getElementById("shape").value);
+    var lakes = document.getElementById("lakes").value;
 
-    let dimensions = prepare.calculate_game_dimensions(
-        board_size, canvas.height, canvas.width, HEX_RADIUS
+    let side = prepare.board_side(board_size);
+    let fit = dicey.fit_layout(
+        side, side, canvas_side, canvas_side, BOARD_MARGIN, orientation, pixel_ratio
     );
-    let tl_point = dimensions[0];
-    let hex_radius = dimensions[1];
-    let side = dimensions[2];
 
-    game = dicey.start_new_game(
-        side,
-        tl_point,
-        hex_radius,
-        parseInt(turns),
-        prepare.player_option_to_code(player1),
-        prepare.player_option_to_code(player2),
-        prepare.player_option_to_code(player3),
-        prepare.player_option_to_code(player4),
-        parseInt(horizon)
-    );
+    let settings = dicey.GameSettings.new(side, fit.start(), fit.radius())
+        .with_move_limit(parseInt(turns))
+        .with_players(
+            prepare.player_option_to_code(player1),
+            prepare.player_option_to_code(player2),
+            prepare.player_option_to_code(player3),
+            prepare.player_option_to_code(player4)
+        )
+        .with_ai_compute_horizon(parseInt(horizon))
+        .with_orientation(orientation)
+        .with_shape(shape)
+        .with_lakes(parseInt(lakes))
+        .with_seed(Math.floor(Math.random() * 4294967295));
+    if (balanced) {
+        settings = settings.with_balance(parseInt(tolerance));
+    }
+    game = dicey.start_new_game(settings);
+    editor = null;
+    tutorial = null;
 
     let player_id = game.current_player_id();
     let player_moves_left = game.current_player_moves_left();
@@ -120,21 +233,382 @@ playButton.addEventListener("click", event => {
     display_player(player_id, player_moves_left, captured_dice, is_ai);
 
     // Kick off our new game
-    ctx.clearRect(0, 0, canvas.width, canvas.height);
-    board.drawGameBoard(ctx, DIE_COLOUR, DOT_COLOUR, game.tessellation());
+    fit_camera(game);
+    draw_game();
     add_battle_log("New game starting!");
     play_on();
 });
 
-// Handle clicks on the canvas
-canvas.addEventListener("click", event => {
+// Play a hand authored map. The player settings only decide who is AI.
+const playMapButton = document.getElementById("play-map");
+playMapButton.addEventListener("click", event => {
+    var map_text = document.getElementById("map-text").value;
+    var player1 = document.getElementById("player1").value;
+    var player2 = document.getElementById("player2").value;
+    var player3 = document.getElementById("player3").value;
+    var player4 = document.getElementById("player4").value;
+    var turns   = document.getElementById("turns").value;
+    var horizon = document.getElementById("horizon").value;
+    let map_error = document.getElementById("map-error");
+
+    try {
+        game = dicey.start_game_from_map(
+            map_text,
+            top_left(),
+            HEX_RADIUS,
+            chosen_orientation(),
+            parseInt(turns),
+            prepare.player_option_to_code(player1),
+            prepare.player_option_to_code(player2),
+            prepare.player_option_to_code(player3),
+            prepare.player_option_to_code(player4),
+            parseInt(horizon)
+        );
+    } catch (e) {
+        map_error.innerText = e;
+        return;
+    }
+    map_error.innerText = "";
+    editor = null;
+    tutorial = null;
+
+    let player_id = game.current_player_id();
+    let player_moves_left = game.current_player_moves_left();
+    let captured_dice = game.current_player_dice_captured();
+    let is_ai = game.current_player_ai();
+    display_player(player_id, player_moves_left, captured_dice, is_ai);
+
+    fit_camera(game);
+    draw_game();
+    add_battle_log("New map game starting!");
+    play_on();
+});
+
+// Fill in the bundled puzzles
+const puzzleSelect = document.getElementById("puzzle");
+for (var i = 0; i < dicey.puzzle_count(); ++i) {
+    let option = document.createElement("option");
+    option.value = i;
+    option.text = dicey.puzzle_name(i);
+    puzzleSelect.appendChild(option);
+}
+
+const display_puzzle = () => {
+    let text = "";
+    switch (game.puzzle_status()) {
+    case dicey.PuzzleStatus.Solving: text = game.puzzle_description(); break;
+    case dicey.PuzzleStatus.Solved: text = "Solved! " + game.puzzle_description(); break;
+    case dicey.PuzzleStatus.Failed: text = "Failed. " + game.puzzle_description(); break;
+    default: break;
+    }
+    document.getElementById("puzzle-status").innerText = text;
+}
+
+const playPuzzleButton = document.getElementById("play-puzzle");
+playPuzzleButton.addEventListener("click", event => {
+    game = dicey.start_puzzle(
+        parseInt(puzzleSelect.value), top_left(), HEX_RADIUS, chosen_orientation()
+    );
+    editor = null;
+    tutorial = null;
+
+    let player_id = game.current_player_id();
+    let player_moves_left = game.current_player_moves_left();
+    let captured_dice = game.current_player_dice_captured();
+    display_player(player_id, player_moves_left, captured_dice, false);
+    display_puzzle();
+
+    fit_camera(game);
+    draw_game();
+    add_battle_log("Puzzle starting!");
+});
+
+const draw_tutorial = () => {
+    let tessellation = tutorial.tessellation();
+    renderer.clear();
+    renderer.draw(tessellation);
+    renderer.draw_highlights(tessellation, tutorial.highlighted_hexes());
+
+    var text = tutorial.outcome();
+    if (tutorial.finished()) {
+        tutorial = null;
+    } else {
+        text += " Step " + tutorial.step_number() + " of " + tutorial.step_count() +
+            ". " + tutorial.instruction();
+    }
+    document.getElementById("puzzle-status").innerText = text;
+}
+
+const playTutorialButton = document.getElementById("play-tutorial");
+playTutorialButton.addEventListener("click", event => {
+    tutorial = dicey.start_tutorial(top_left(), HEX_RADIUS, chosen_orientation());
+    editor = null;
+    fit_camera(tutorial);
+    draw_tutorial();
+});
+
+// Put the current position in the URL fragment so it can be shared.
+const sharePositionButton = document.getElementById("share-position");
+sharePositionButton.addEventListener("click", event => {
+    window.location.hash = game.position();
+});
+
+// Download the board as it is now as an SVG picture.
+const saveSvgButton = document.getElementById("save-svg");
+saveSvgButton.addEventListener("click", event => {
+    let svg = dicey.board_svg(game.tessellation(), DIE_COLOUR, DOT_COLOUR);
+    let url = URL.createObjectURL(new Blob([svg], { type: "image/svg+xml" }));
+    let link = document.createElement("a");
+    link.href = url;
+    link.download = "dicey-board.svg";
+    link.click();
+    URL.revokeObjectURL(url);
+});
+
+// Codes of the four player seats as chosen.
+const player_codes = () => {
+    var codes = [];
+    for (var i = 1; i <= 4; ++i) {
+        let option = document.getElementById("player" + i).value;
+        codes.push(prepare.player_option_to_code(option));
+    }
+    return codes;
+}
+
+const draw_editor = () => {
+    tutorial = null;
+    renderer.clear();
+    renderer.draw(editor.tessellation());
+    document.getElementById("map-error").innerText = editor.validate();
+}
+
+const editBoardButton = document.getElementById("edit-board");
+editBoardButton.addEventListener("click", event => {
+    var board_size = document.getElementById("board-size").value;
+    let side = prepare.board_side(board_size);
+    let orientation = chosen_orientation();
+    let fit = dicey.fit_layout(
+        side,
+        side,
+        canvas_side,
+        canvas_side,
+        BOARD_MARGIN,
+        orientation,
+        pixel_ratio
+    );
+    editor = dicey.start_editor(
+        side, side, fit.start(), fit.radius(), orientation, ...player_codes()
+    );
+    fit_camera(editor);
+    draw_editor();
+});
+
+const editMapButton = document.getElementById("edit-map");
+editMapButton.addEventListener("click", event => {
+    var map_text = document.getElementById("map-text").value;
+    try {
+        editor = dicey.edit_map(
+            map_text, top_left(), HEX_RADIUS, chosen_orientation(), ...player_codes()
+        );
+    } catch (e) {
+        document.getElementById("map-error").innerText = e;
+        return;
+    }
+    fit_camera(editor);
+    draw_editor();
+});
+
+// Keep the editor's idea of who is playing up to date so it validates against them.
+for (var i = 1; i <= 4; ++i) {
+    document.getElementById("player" + i).addEventListener("change", event => {
+        if (editor != null) {
+            editor.choose_players(...player_codes());
+            draw_editor();
+        }
+    });
+}
+
+const exportEditedButton = document.getElementById("export-edited");
+exportEditedButton.addEventListener("click", event => {
+    if (editor != null) {
+        document.getElementById("map-text").value = editor.export_map();
+    }
+});
+
+const playEditedButton = document.getElementById("play-edited");
+playEditedButton.addEventListener("click", event => {
+    if (editor == null) {
+        return;
+    }
+    var player1 = document.getElementById("player1").value;
+    var player2 = document.getElementById("player2").value;
+    var player3 = document.getElementById("player3").value;
+    var player4 = document.getElementById("player4").value;
+    var turns   = document.getElementById("turns").value;
+    var horizon = document.getElementById("horizon").value;
+
+    try {
+        game = editor.start_game(
+            parseInt(turns),
+            prepare.player_option_to_code(player1),
+            prepare.player_option_to_code(player2),
+            prepare.player_option_to_code(player3),
+            prepare.player_option_to_code(player4),
+            parseInt(horizon)
+        );
+    } catch (e) {
+        document.getElementById("map-error").innerText = e;
+        return;
+    }
+    editor = null;
+
+    fit_camera(game);
+    draw_game();
+    add_battle_log("Edited board starting!");
+    play_on();
+});
+
+// Convert a mouse event to a point on the canvas.
+const canvas_point = event => {
     const boundingRect = canvas.getBoundingClientRect();
     const x = event.clientX - boundingRect.left;
     const y = event.clientY - boundingRect.top;
+    return dicey.Point.new(x, y);
+}
 
-    // Offset the (x,y) coordinate since the grid is not exactly aligned with the canvas
-    var board_start = game.tessellation().start_hex_center();
-    var board_coord = dicey.Point.new(x - board_start.x(), y - board_start.y());
+// Convert a mouse event to the point on the board under it, wherever the camera is.
+const board_point = event => camera.board_point(canvas_point(event));
+
+// Draw whatever is being shown from scratch, such as after the camera moves.
+const redraw = () => {
+    if (tutorial != null) {
+        draw_tutorial();
+    } else if (editor != null) {
+        draw_editor();
+    } else {
+        draw_game();
+    }
+}
+
+// The board being shown, whether that's the tutorial, the editor or a game.
+const shown_board = () => {
+    if (tutorial != null) {
+        return tutorial;
+    } else if (editor != null) {
+        return editor;
+    }
+    return game;
+}
+
+const fitBoardButton = document.getElementById("fit-board");
+fitBoardButton.addEventListener("click", event => {
+    fit_camera(shown_board());
+    redraw();
+});
+
+// Pan by dragging. A drag isn't also taken as a click on the board.
+var drag = null;
+var dragged = false;
+
+// The last mouse move over the canvas so keys can edit the hex under it. The event is
+// kept rather than a point since points are used up when handed to the board.
+var hover = null;
+canvas.addEventListener("mousemove", event => {
+    hover = event;
+});
+canvas.addEventListener("mouseleave", event => {
+    hover = null;
+});
+canvas.addEventListener("mousedown", event => {
+    drag = { x: event.clientX, y: event.clientY };
+    dragged = false;
+});
+canvas.addEventListener("mousemove", event => {
+    if (drag == null) {
+        return;
+    }
+    let dx = event.clientX - drag.x;
+    let dy = event.clientY - drag.y;
+    if (!dragged && Math.abs(dx) + Math.abs(dy) < 4) {
+        return;
+    }
+    dragged = true;
+    drag = { x: event.clientX, y: event.clientY };
+    camera.pan(dx, dy);
+    renderer.set_camera(camera);
+    redraw();
+});
+window.addEventListener("mouseup", event => {
+    drag = null;
+});
+
+// Scrolling zooms around the mouse, except in the editor where it changes the dice.
+canvas.addEventListener("wheel", event => {
+    event.preventDefault();
+    if (editor == null) {
+        camera.zoom_at(event.deltaY < 0 ? 1.1 : 1 / 1.1, canvas_point(event));
+        renderer.set_camera(camera);
+        redraw();
+        return;
+    }
+    let board_coord = board_point(event);
+    if (editor.change_dice_with_pixel(board_coord, event.deltaY < 0 ? 1 : -1)) {
+        draw_editor();
+    }
+});
+
+// Edit the hex under the mouse with the keyboard. `o` changes the owner, `+` and `-`
+// change the dice and `b` blocks it, floods it, takes it away or puts it back.
+window.addEventListener("keydown", event => {
+    if (editor == null || hover == null) {
+        return;
+    }
+    let tag = event.target.tagName;
+    if (tag == "INPUT" || tag == "TEXTAREA" || tag == "SELECT") {
+        return;
+    }
+
+    var edited;
+    switch (event.key) {
+    case "o": edited = editor.cycle_owner_with_pixel(board_point(hover)); break;
+    case "+":
+    case "=": edited = editor.change_dice_with_pixel(board_point(hover), 1); break;
+    case "-": edited = editor.change_dice_with_pixel(board_point(hover), -1); break;
+    case "b": edited = editor.toggle_hex_with_pixel(board_point(hover)); break;
+    default : return;
+    }
+    event.preventDefault();
+    if (edited) {
+        draw_editor();
+    }
+});
+
+// Handle clicks on the canvas
+canvas.addEventListener("click", event => {
+    if (dragged) {
+        dragged = false;
+        return;
+    }
+
+    if (tutorial != null) {
+        let board_coord = board_point(event);
+        tutorial.select_hex_with_pixel(board_coord);
+        draw_tutorial();
+        return;
+    }
+
+    if (editor != null) {
+        let board_coord = board_point(event);
+        let edited = event.shiftKey
+            ? editor.toggle_hex_with_pixel(board_coord)
+            : editor.cycle_owner_with_pixel(board_coord);
+        if (edited) {
+            draw_editor();
+        }
+        return;
+    }
+
+    var board_coord = board_point(event);
 
     // Forward this coordinate to the game state and let it do its thing.
     if (game.select_hex_with_pixel(board_coord)) {
@@ -147,11 +621,24 @@ canvas.addEventListener("click", event => {
     let player_moves_left = game.current_player_moves_left();
     let captured_dice = game.current_player_dice_captured();
     display_player(player_id, player_moves_left, captured_dice);
+    display_puzzle();
     
     // Finally, we draw the board. It could have changed!
-    board.drawGameBoard(ctx, DIE_COLOUR, DOT_COLOUR, game.tessellation());
+    draw_dirty();
     play_on();
 });
 
-board.drawGameBoard(ctx, DIE_COLOUR, DOT_COLOUR, game.tessellation());
+// Lay the game out again when the window changes size. Only games can be laid out again
+// so the tutorial and editor just have the camera fit them.
+window.addEventListener("resize", event => {
+    size_canvas();
+    if (tutorial == null && editor == null) {
+        game.relayout(canvas_side, canvas_side, pixel_ratio);
+    }
+    fit_camera(shown_board());
+    redraw();
+});
+
+fit_camera(game);
+draw_game();
 
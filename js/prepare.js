@@ -2,42 +2,18 @@
 
 import * as dicey from "../crate/pkg/wasm_dicey";
 
-// The canvas is expected to be square.
-export const calculate_game_dimensions = (board_size, canvas_side) => {
-    switch (board_size) {
-    case "2x2": {
-        let hex_radius = canvas_side / 4.5;
-        return [dicey.Point.new(hex_radius, hex_radius), hex_radius, 2];
-    };
-    case "3x3": {
-        let hex_radius = canvas_side / 6.3;
-        return [dicey.Point.new(hex_radius, hex_radius), hex_radius, 3];
-    };
-    case "4x4": {
-        let hex_radius = canvas_side / 8;
-        return [dicey.Point.new(hex_radius, hex_radius), hex_radius, 4];
-    };
-    case "5x5": {
-        let hex_radius = canvas_side / 10;
-        return [dicey.Point.new(hex_radius, hex_radius), hex_radius, 5];
-    };
-    case "6x6": {
-        let hex_radius = canvas_side / 12;
-        return [dicey.Point.new(hex_radius, hex_radius), hex_radius, 6];
-    };
-    case "7x7": {
-        let hex_radius = canvas_side / 14;
-        return [dicey.Point.new(hex_radius, hex_radius), hex_radius, 7];
-    };
-    case "8x8": {
-        let hex_radius = canvas_side / 16;
-        return [dicey.Point.new(hex_radius, hex_radius), hex_radius, 8];
-    };
-    default: {
-        let hex_radius = canvas_side / 16;
-        return [dicey.Point.new(hex_radius, hex_radius), hex_radius, 2];
+// Number of hexes along each side of a board size such as "8x8". The board is laid out
+// to fit the canvas by `dicey.fit_layout` whatever the size.
+export const board_side = (board_size) => {
+    return parseInt(board_size) || 2;
+}
+
+export const shape_option_to_code = (option) => {
+    switch (option) {
+    case "hexagon" : return dicey.Shape.Hexagon;
+    case "triangle": return dicey.Shape.Triangle;
+    default        : return dicey.Shape.Rectangle;
     };
-    }
 }
 
 export const player_option_to_code = (option) => {